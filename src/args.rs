@@ -1,14 +1,80 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgEnum, Parser, Subcommand};
+
+use crate::{export::Format as ExportFormat, mode::DEFAULT_SESSION_DIR};
 
 #[derive(Parser, Debug)]
-#[clap(version)]
+#[clap(version, subcommand_negates_reqs = true)]
 pub struct Args {
   /// Source directory for gopro files
   #[clap(short, long)]
   pub input_dir: Option<PathBuf>,
 
   /// Destination directory for categorized files
-  pub output_dir: PathBuf,
+  #[clap(required = true)]
+  pub output_dir: Option<PathBuf>,
+
+  /// Name of the directories sessions are imported into, `{date}`, `{place}` and `{country}` are replaced
+  #[clap(short, long, default_value = DEFAULT_SESSION_DIR)]
+  pub session_dir: String,
+
+  /// Directory to export tracks to, instead of the directory of their session
+  #[clap(short, long)]
+  pub export_dir: Option<PathBuf>,
+
+  #[clap(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Print the parsed GPMF telemetry of a file
+  DumpGpmf {
+    /// The MP4 file to read
+    file: PathBuf,
+
+    /// Output format
+    #[clap(short, long, arg_enum, default_value = "text")]
+    format: DumpFormat,
+
+    /// Skip damaged DEVC blocks instead of stopping at the first error
+    #[clap(short, long)]
+    recover: bool,
+  },
+
+  /// Export the GPS track of one or more files, chapters of the same recording are joined
+  Export {
+    /// MP4 files, or directories to search for them
+    #[clap(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output format
+    #[clap(short, long, arg_enum, default_value = "gpx")]
+    format: ExportFormat,
+
+    /// File to write to, instead of stdout
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Name of the track, defaults to the name of the first path
+    #[clap(short, long)]
+    name: Option<String>,
+  },
+
+  /// Export every sensor stream of a file to csv, one file per stream
+  ExportCsv {
+    /// The MP4 file to read
+    file: PathBuf,
+
+    /// Directory to write the csv files to
+    #[clap(short, long, default_value = ".")]
+    output_dir: PathBuf,
+  },
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum DumpFormat {
+  Json,
+  Text,
 }
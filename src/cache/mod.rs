@@ -5,6 +5,7 @@ use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+pub use self::user::Import;
 use self::{
   user::User,
   version::{Local, SerialNumber, Version},
@@ -15,26 +16,40 @@ use crate::{
   mode::Mode,
   ui::state::{
     destination::Destination,
-    session::{Date, File as UiFile, Session as UiSession, Status as UiFileStatus},
+    session::{
+      Camera as UiCamera, Date, File as UiFile, Profile as UiProfile, Session as UiSession, Stats as UiStats, Status as UiFileStatus,
+      Video as UiVideo,
+    },
   },
 };
 
-type FileName = String;
+// a file's media unique ID, or its name for files without one.
+type FileKey = String;
+
+const UNKNOWN_SERIAL: &str = "unknown";
+
+// Bumped whenever more is read from files, so that entries cached before are read again once.
+pub const PROBED: u32 = 1;
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Source {
   #[serde(skip)]
   serial: SerialNumber,
 
-  files: BTreeMap<FileName, File>,
+  files: BTreeMap<FileKey, File>,
   session_destinations: BTreeMap<Date, Destination>,
 }
 
 impl Source {
   pub fn from(mode: &Mode) -> Result<Self> {
     let serial = match &mode {
-      Mode::Importing { input_dir, .. } => Version::from(input_dir)?.camera_serial_number,
-      Mode::Viewing { input_dir } => Local::from(input_dir)?.id.to_string(),
+      // sources without MISC/version.txt are keyed by the serial number in their clips, or share a
+      // cache whose files are told apart by their media unique IDs.
+      Mode::Importing { input_dir, .. } => match Version::from(input_dir) {
+        Ok(version) => version.camera_serial_number,
+        Err(_) => Version::clips(input_dir).unwrap_or_else(|| UNKNOWN_SERIAL.to_string()),
+      },
+      Mode::Viewing { input_dir, .. } => Local::from(input_dir)?.id.to_string(),
     };
 
     let mut user_cache = User::from(&dirs::config_json()?)?.sources.remove(&serial).unwrap_or_default();
@@ -44,22 +59,32 @@ impl Source {
     Ok(user_cache)
   }
 
-  pub fn get(&self, file_name: &str) -> Option<File> {
-    self.files.get(file_name).cloned()
+  pub fn get(&self, key: &str) -> Option<File> {
+    self.files.get(key).cloned()
   }
 
+  // Caches every chapter of a recording separately.
   pub fn set(&mut self, file: &UiFile) -> Result<()> {
-    self.files.insert(
-      file.name()?,
-      File {
-        date: file.date.clone(),
-        seconds: file.seconds,
-        note: file.note.clone(),
-        status: file.status.clone(),
-
-        imported: false,
-      },
-    );
+    for chapter in file.recording() {
+      self.files.insert(
+        chapter.key()?,
+        File {
+          date: chapter.date.clone(),
+          timezone: chapter.timezone.clone(),
+          seconds: chapter.seconds,
+          video: chapter.video.clone(),
+          camera: chapter.camera.clone(),
+          stats: chapter.stats.clone(),
+          profile: chapter.profile.clone(),
+          route: chapter.route.clone(),
+          note: chapter.note.clone(),
+          status: chapter.status.clone(),
+
+          probed: PROBED,
+          imported: false,
+        },
+      );
+    }
 
     Ok(())
   }
@@ -81,12 +106,39 @@ impl Source {
   }
 }
 
+// The import settings of the user's config.
+pub fn import_config() -> Result<Import> {
+  Ok(User::from(&dirs::config_json()?)?.import)
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct File {
   pub date: String,
+  pub timezone: Option<String>,
   pub seconds: f64,
+  pub video: Option<UiVideo>,
+  pub camera: Option<UiCamera>,
+  pub stats: Option<UiStats>,
+  pub profile: Option<UiProfile>,
+  #[serde(default)]
+  pub route: Vec<(f64, f64)>,
   pub note: Option<String>,
   pub status: Option<UiFileStatus>,
 
+  // the `PROBED` version the entry was read with, fields it lacks since then weren't in the file.
+  #[serde(default)]
+  pub probed: u32,
   pub imported: bool,
 }
+
+impl File {
+  // Whether the entry has everything read from the file, entries written before a field was
+  // cached lack it.
+  pub fn is_complete(&self) -> bool {
+    self.probed >= PROBED
+  }
+
+  pub fn has_track(&self) -> bool {
+    self.stats.is_some() && self.profile.is_some() && !self.route.is_empty()
+  }
+}
@@ -8,7 +8,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use super::{version::SerialNumber, Source};
-use crate::error::Result;
+use crate::{error::Result, ui::state::session::SidecarKind};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -16,6 +16,9 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub struct User {
   pub version: String,
   pub sources: BTreeMap<SerialNumber, Source>,
+
+  #[serde(default)]
+  pub import: Import,
 }
 
 impl Default for User {
@@ -23,6 +26,36 @@ impl Default for User {
     Self {
       version: VERSION.to_string(),
       sources: BTreeMap::default(),
+      import: Import::default(),
+    }
+  }
+}
+
+// Which sidecar files are copied along with the videos they belong to.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Import {
+  pub lrv: bool,
+  pub thm: bool,
+  pub wav: bool,
+}
+
+impl Default for Import {
+  fn default() -> Self {
+    Self {
+      lrv: false,
+      thm: false,
+      wav: true,
+    }
+  }
+}
+
+impl Import {
+  pub fn copies(&self, kind: SidecarKind) -> bool {
+    match kind {
+      SidecarKind::Lrv => self.lrv,
+      SidecarKind::Thm => self.thm,
+      SidecarKind::Wav => self.wav,
     }
   }
 }
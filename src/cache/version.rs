@@ -2,25 +2,14 @@ use std::{fs, path::Path};
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use walkdir::WalkDir;
 
-use crate::error::Result;
+use crate::{error::Result, reader::mp4::Mp4};
 
 pub type SerialNumber = String;
 
 #[derive(Deserialize)]
 pub struct Version {
-  #[serde(alias = "info version")]
-  pub info_version: String,
-
-  #[serde(alias = "firmware version")]
-  pub firmware_version: String,
-
-  #[serde(alias = "wifi mac")]
-  pub wifi_mac: String,
-
-  #[serde(alias = "camera type")]
-  pub camera_type: String,
-
   #[serde(alias = "camera serial number")]
   pub camera_serial_number: String,
 }
@@ -31,6 +20,19 @@ impl Version {
 
     Ok(serde_json::from_str(&version_json.replace(",\n}", "}"))?)
   }
+
+  // The camera serial number of a source without MISC/version.txt, from the udta box of its
+  // first clip. Cameras without a CASN entry in udta have none.
+  pub fn clips(path: &Path) -> Option<SerialNumber> {
+    let mp4 = WalkDir::new(path.join("DCIM"))
+      .sort_by_file_name()
+      .into_iter()
+      .filter_map(std::result::Result::ok)
+      .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "MP4"))
+      .find_map(|entry| Mp4::open(entry.path()).ok())?;
+
+    mp4.camera().ok()?.serial
+  }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -0,0 +1,84 @@
+use std::{
+  io::{self, Write},
+  path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+  args::DumpFormat,
+  error::Result,
+  reader::{gpmf::Gpmf, mp4::Mp4},
+};
+
+pub fn run(path: &Path, format: DumpFormat, recover: bool) -> Result<()> {
+  let mut mp4 = Mp4::open(path)?;
+  let mut payloads = mp4.gpmf_payloads()?;
+  if recover {
+    payloads = payloads.recovering();
+  }
+
+  let mut gpmf = Vec::new();
+  for payload in payloads.by_ref() {
+    gpmf.extend(payload?);
+  }
+
+  let mut stdout = io::stdout().lock();
+  match format {
+    DumpFormat::Json => {
+      serde_json::to_writer_pretty(&mut stdout, &gpmf)?;
+      writeln!(stdout)?;
+    }
+
+    DumpFormat::Text => text(&mut stdout, &gpmf, 0)?,
+  }
+
+  for (offset, error) in payloads.errors() {
+    eprintln!("{} (skipped, in the gpmd sample at byte {})", error, offset);
+  }
+
+  Ok(())
+}
+
+// Writes one line per entry, indenting the entries of each DEVC and STRM.
+fn text(out: &mut impl Write, gpmf: &[Gpmf], depth: usize) -> Result<()> {
+  for entry in gpmf {
+    let indent = "  ".repeat(depth);
+    let key = entry.key();
+
+    match entry {
+      Gpmf::Klv { labels, entries, .. } => {
+        let name = labels.name.as_ref().or(labels.device.as_ref());
+        let units = if labels.units.is_empty() {
+          String::new()
+        } else {
+          format!(" ({})", labels.units.join(", "))
+        };
+
+        writeln!(out, "{}{} {}{}", indent, key, name.map_or("", String::as_str), units)?;
+        text(out, entries, depth + 1)?;
+      }
+
+      Gpmf::Gps5 { entries } => writeln!(out, "{}{} {}", indent, key, samples(entries)?)?,
+      Gpmf::Gps9 { entries } => writeln!(out, "{}{} {}", indent, key, samples(entries)?)?,
+      Gpmf::Accl { entries } | Gpmf::Gyro { entries } | Gpmf::Magn { entries } => writeln!(out, "{}{} {}", indent, key, samples(entries)?)?,
+      Gpmf::GpsTime { time } => writeln!(out, "{}{} {}", indent, key, time)?,
+      Gpmf::GpsFix { fix } => writeln!(out, "{}{} {}", indent, key, serde_json::to_string(fix)?)?,
+      Gpmf::GpsPrecision { dop } => writeln!(out, "{}{} {}", indent, key, dop)?,
+      Gpmf::Scale { divisors } => writeln!(out, "{}{} {}", indent, key, serde_json::to_string(divisors)?)?,
+      Gpmf::Values { kind, samples: values, .. } => writeln!(out, "{}{} {} {}", indent, key, kind, samples(values)?)?,
+      Gpmf::Unknown { kind, size, count, .. } => writeln!(out, "{}{} {} {}x{} bytes, not decoded", indent, key, kind, size, count)?,
+    }
+  }
+
+  Ok(())
+}
+
+// A single sample as is, or the number of samples and the first one.
+fn samples<T: Serialize>(samples: &[T]) -> Result<String> {
+  Ok(match samples {
+    [] => "no samples".to_string(),
+    [sample] => serde_json::to_string(sample)?,
+    [first, ..] => format!("{} samples, first {}", samples.len(), serde_json::to_string(first)?),
+  })
+}
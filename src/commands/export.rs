@@ -0,0 +1,60 @@
+use std::{
+  io,
+  path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+use crate::{
+  error::{err, Result},
+  export::{csv, Format, Track},
+  utils,
+};
+
+pub fn run(paths: &[PathBuf], format: Format, output: Option<&Path>, name: Option<String>) -> Result<()> {
+  let files = mp4_files(paths)?;
+  if files.is_empty() {
+    return Err(err!("No MP4 files found"));
+  }
+
+  let name = match name {
+    Some(name) => name,
+    None => paths[0].file_stem().unwrap_or_default().to_string_lossy().to_string(),
+  };
+
+  let track = Track::read(name, &files)?;
+
+  match output {
+    Some(output) => track.save(format, output),
+    None => track.write(format, &mut io::stdout().lock()),
+  }
+}
+
+pub fn csv(path: &Path, output_dir: &Path) -> Result<()> {
+  for csv_path in csv::save(path, output_dir)? {
+    println!("{}", csv_path.display());
+  }
+
+  Ok(())
+}
+
+// The MP4 files of `paths`, ordered so that chapters of the same recording are consecutive.
+fn mp4_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+
+  for path in paths {
+    for entry in WalkDir::new(path).into_iter() {
+      let entry = entry?;
+
+      if entry.file_type().is_file() && matches!(entry.path().extension(), Some(ext) if ext.eq_ignore_ascii_case("MP4")) {
+        files.push(entry.into_path());
+      }
+    }
+  }
+
+  let names: Vec<String> = files.iter().map(|file| utils::file_name(file)).collect::<Result<_>>()?;
+  let mut files: Vec<(String, PathBuf)> = names.into_iter().zip(files).collect();
+  files.sort_by_key(|(name, _)| (utils::chapter(name), name.clone()));
+
+  Ok(files.into_iter().map(|(_, file)| file).collect())
+}
@@ -0,0 +1,17 @@
+mod dump_gpmf;
+mod export;
+
+use crate::{args::Command, error::Result};
+
+pub fn run(command: Command) -> Result<()> {
+  match command {
+    Command::DumpGpmf { file, format, recover } => dump_gpmf::run(&file, format, recover),
+    Command::Export {
+      paths,
+      format,
+      output,
+      name,
+    } => export::run(&paths, format, output.as_deref(), name),
+    Command::ExportCsv { file, output_dir } => export::csv(&file, &output_dir),
+  }
+}
@@ -0,0 +1,190 @@
+use std::{
+  collections::BTreeMap,
+  fs, iter,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  error::Result,
+  reader::{
+    gpmf::{
+      types::{Fix, Labels},
+      value::Value,
+      Gpmf,
+    },
+    mp4::Mp4,
+  },
+};
+
+// The samples of one stream of a clip, as csv lines.
+struct Table {
+  header: Vec<String>,
+  lines: Vec<String>,
+}
+
+// Writes one csv file per stream of the clip at `path` into `dir`, named `{clip}-{KEY}.csv`.
+// Streams with the same key from different devices are told apart by the device name.
+pub fn save(path: &Path, dir: &Path) -> Result<Vec<PathBuf>> {
+  let mut mp4 = Mp4::open(path)?;
+  let mut tables = BTreeMap::new();
+
+  for payload in mp4.gpmf_payloads()?.recovering() {
+    collect(&payload?, &mut tables);
+  }
+
+  let is_multi_device = |key: &str| tables.keys().filter(|(_, k)| k == key).count() > 1;
+  let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+  let mut paths = Vec::new();
+  for ((device, key), table) in &tables {
+    let name = match device {
+      Some(device) if is_multi_device(key) => format!("{}-{}-{}.csv", stem, device, key),
+      _ => format!("{}-{}.csv", stem, key),
+    };
+
+    let csv_path = dir.join(name);
+    fs::write(&csv_path, format!("{}\n{}\n", table.header.join(","), table.lines.join("\n")))?;
+    paths.push(csv_path);
+  }
+
+  Ok(paths)
+}
+
+fn collect(gpmf: &[Gpmf], tables: &mut BTreeMap<(Option<String>, String), Table>) {
+  for entry in gpmf {
+    match entry {
+      // the samples of a stream are always its last entry.
+      Gpmf::Klv { key, labels, entries } if key == "STRM" => {
+        if let Some(data) = entries.last() {
+          let rows = rows(data);
+          if rows.is_empty() {
+            continue;
+          }
+
+          let table = tables
+            .entry((labels.device.clone(), data.key().to_string()))
+            .or_insert_with(|| Table {
+              header: header(data, labels, rows[0].len() - 1),
+              lines: Vec::new(),
+            });
+
+          table.lines.extend(rows.into_iter().map(|row| row.join(",")));
+        }
+      }
+
+      Gpmf::Klv { entries, .. } => collect(entries, tables),
+      _ => (),
+    }
+  }
+}
+
+// The column names of a stream, labelled with their units.
+fn header(data: &Gpmf, labels: &Labels, columns: usize) -> Vec<String> {
+  let names: Vec<String> = match data {
+    Gpmf::Gps5 { .. } => ["latitude", "longitude", "altitude", "speed_2d", "speed_3d"]
+      .map(String::from)
+      .to_vec(),
+    Gpmf::Gps9 { .. } => ["latitude", "longitude", "altitude", "speed_2d", "speed_3d", "utc", "dop", "fix"]
+      .map(String::from)
+      .to_vec(),
+    Gpmf::Accl { .. } | Gpmf::Gyro { .. } | Gpmf::Magn { .. } => ["x", "y", "z"].map(String::from).to_vec(),
+    _ if columns == 1 => vec![data.key().to_lowercase()],
+    _ => (0..columns).map(|i| format!("{}_{}", data.key().to_lowercase(), i)).collect(),
+  };
+
+  // GPS9 units also describe its days and seconds, which are combined into `utc`.
+  let units = match data {
+    Gpmf::Gps9 { .. } => &labels.units[..labels.units.len().min(5)],
+    _ => &labels.units[..],
+  };
+
+  let unit = |i: usize| if units.len() == 1 { units.first() } else { units.get(i) };
+
+  let mut header = vec!["time (s)".to_string()];
+  header.extend(names.into_iter().enumerate().map(|(i, name)| match unit(i) {
+    Some(unit) => quote(&format!("{} ({})", name, unit)),
+    None => quote(&name),
+  }));
+
+  header
+}
+
+// One row per sample, starting with its clip-relative time.
+fn rows(data: &Gpmf) -> Vec<Vec<String>> {
+  match data {
+    Gpmf::Gps5 { entries } => entries
+      .iter()
+      .map(|gps5| {
+        row(
+          gps5.time,
+          [gps5.latitude, gps5.longitude, gps5.altitude, gps5.speed_2d, gps5.speed_3d]
+            .map(|x| x.to_string())
+            .to_vec(),
+        )
+      })
+      .collect(),
+
+    Gpmf::Gps9 { entries } => entries
+      .iter()
+      .map(|gps9| {
+        let mut values = [gps9.latitude, gps9.longitude, gps9.altitude, gps9.speed_2d, gps9.speed_3d]
+          .map(|x| x.to_string())
+          .to_vec();
+        values.extend([
+          gps9.utc.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+          gps9.dop.to_string(),
+          fix(gps9.fix).to_string(),
+        ]);
+
+        row(gps9.time, values)
+      })
+      .collect(),
+
+    Gpmf::Accl { entries } | Gpmf::Gyro { entries } | Gpmf::Magn { entries } => entries
+      .iter()
+      .map(|xyz| row(xyz.time, [xyz.x, xyz.y, xyz.z].map(|x| x.to_string()).to_vec()))
+      .collect(),
+
+    Gpmf::Values { samples, times, .. } if !times.is_empty() => iter::zip(times, samples)
+      .map(|(time, sample)| row(*time, sample.iter().map(field).collect()))
+      .collect(),
+
+    _ => Vec::new(),
+  }
+}
+
+fn row(time: f64, values: Vec<String>) -> Vec<String> {
+  let mut row = vec![format!("{:.6}", time)];
+  row.extend(values);
+
+  row
+}
+
+fn field(value: &Value) -> String {
+  match value {
+    Value::DateTime(datetime) => datetime.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+    Value::Uuid(uuid) => uuid.to_string(),
+    value => match (value.as_f64(), value.as_str()) {
+      (Some(x), _) => x.to_string(),
+      (_, Some(s)) => quote(s),
+      _ => String::new(),
+    },
+  }
+}
+
+// The fix type as reported by GPSF.
+fn fix(fix: Fix) -> u8 {
+  match fix {
+    Fix::None => 0,
+    Fix::TwoD => 2,
+    Fix::ThreeD => 3,
+  }
+}
+
+fn quote(field: &str) -> String {
+  if field.contains([',', '"', '\n']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
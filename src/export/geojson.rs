@@ -0,0 +1,99 @@
+use std::io::Write;
+
+use serde_json::{json, Value};
+
+use super::{Clip, Track};
+use crate::error::Result;
+
+// Writes a GeoJSON feature collection, with one feature per clip.
+pub fn write(track: &Track, out: &mut impl Write) -> Result<()> {
+  let collection = json!({
+    "type": "FeatureCollection",
+    "name": track.name,
+    "features": track.clips.iter().map(feature).collect::<Vec<_>>(),
+  });
+
+  serde_json::to_writer(&mut *out, &collection)?;
+  writeln!(out)?;
+
+  Ok(())
+}
+
+fn feature(clip: &Clip) -> Value {
+  let coordinates: Vec<_> = clip
+    .points
+    .iter()
+    .map(|point| [point.longitude, point.latitude, point.altitude])
+    .collect();
+
+  // a LineString needs at least two positions, clips with a single fix are a Point.
+  let geometry = match coordinates.as_slice() {
+    [] => Value::Null,
+    [coordinate] => json!({ "type": "Point", "coordinates": coordinate }),
+    _ => json!({ "type": "LineString", "coordinates": coordinates }),
+  };
+
+  json!({
+    "type": "Feature",
+    "geometry": geometry,
+    "properties": {
+      "file": clip.name,
+      "note": clip.note,
+      "duration": clip.seconds,
+      "status": clip.status(),
+    },
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{json, Value};
+
+  use super::super::{
+    tests::{clip, written},
+    Format,
+  };
+
+  fn geometries(fixes: &[usize]) -> Vec<Value> {
+    let clips = fixes
+      .iter()
+      .enumerate()
+      .map(|(i, fixes)| clip(&format!("GX01012{}.MP4", i), *fixes))
+      .collect();
+    let collection: Value = serde_json::from_str(&written(Format::GeoJson, clips)).unwrap();
+
+    assert_eq!(collection["name"], "Mont Blanc");
+
+    collection["features"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|feature| feature["geometry"].clone())
+      .collect()
+  }
+
+  #[test]
+  fn no_fixes() {
+    assert_eq!(geometries(&[]), Vec::<Value>::new());
+    assert_eq!(geometries(&[0]), vec![Value::Null]);
+  }
+
+  #[test]
+  fn single_fix() {
+    assert_eq!(
+      geometries(&[1]),
+      vec![json!({ "type": "Point", "coordinates": [6.0, 45.0, 1000.0] })]
+    );
+  }
+
+  #[test]
+  fn many_fixes() {
+    assert_eq!(
+      geometries(&[2, 0]),
+      vec![
+        json!({ "type": "LineString", "coordinates": [[6.0, 45.0, 1000.0], [6.0, 45.001, 1001.0]] }),
+        Value::Null,
+      ]
+    );
+  }
+}
@@ -0,0 +1,77 @@
+use std::io::Write;
+
+use super::{escape, Track};
+use crate::error::Result;
+
+// Writes a GPX 1.1 track, with one segment per recording.
+pub fn write(track: &Track, out: &mut impl Write) -> Result<()> {
+  writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+  writeln!(
+    out,
+    r#"<gpx version="1.1" creator="{}" xmlns="http://www.topografix.com/GPX/1/1">"#,
+    env!("CARGO_PKG_NAME")
+  )?;
+  writeln!(out, "  <trk>")?;
+  writeln!(out, "    <name>{}</name>", escape(&track.name))?;
+
+  for recording in track.recordings() {
+    writeln!(out, "    <trkseg>")?;
+
+    for point in recording.iter().flat_map(|clip| &clip.points) {
+      write!(
+        out,
+        r#"      <trkpt lat="{:.7}" lon="{:.7}"><ele>{:.3}</ele>"#,
+        point.latitude, point.longitude, point.altitude
+      )?;
+
+      if let Some(utc) = point.utc {
+        write!(out, "<time>{}Z</time>", utc.format("%Y-%m-%dT%H:%M:%S%.3f"))?;
+      }
+
+      writeln!(out, "</trkpt>")?;
+    }
+
+    writeln!(out, "    </trkseg>")?;
+  }
+
+  writeln!(out, "  </trk>")?;
+  writeln!(out, "</gpx>")?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{
+    tests::{clip, written},
+    Format,
+  };
+
+  #[test]
+  fn no_fixes() {
+    let gpx = written(Format::Gpx, vec![clip("GX010123.MP4", 0)]);
+
+    assert!(gpx.contains("<name>Mont Blanc</name>"));
+    assert_eq!(gpx.matches("<trkseg>").count(), 1);
+    assert!(!gpx.contains("<trkpt"));
+  }
+
+  #[test]
+  fn single_fix() {
+    let gpx = written(Format::Gpx, vec![clip("GX010123.MP4", 1)]);
+
+    assert!(gpx.contains(r#"<trkpt lat="45.0000000" lon="6.0000000"><ele>1000.000</ele><time>2023-04-05T12:00:00.500Z</time></trkpt>"#));
+  }
+
+  #[test]
+  fn chapters_share_a_segment() {
+    let gpx = written(
+      Format::Gpx,
+      vec![clip("GX010123.MP4", 3), clip("GX020123.MP4", 2), clip("GX010124.MP4", 2)],
+    );
+
+    assert_eq!(gpx.matches("<trkseg>").count(), 2);
+    assert_eq!(gpx.matches("<trkpt").count(), 7);
+    assert!(gpx.trim_end().ends_with("</gpx>"));
+  }
+}
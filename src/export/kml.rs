@@ -0,0 +1,100 @@
+use std::io::Write;
+
+use super::{escape, Clip, Track};
+use crate::error::Result;
+
+// Writes a KML document, with one placemark per clip with GPS fixes. A LineString needs at least
+// two coordinates, so clips with a single fix are written as a Point.
+pub fn write(track: &Track, out: &mut impl Write) -> Result<()> {
+  writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+  writeln!(out, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+  writeln!(out, "  <Document>")?;
+  writeln!(out, "    <name>{}</name>", escape(&track.name))?;
+
+  for clip in track.clips.iter().filter(|clip| !clip.points.is_empty()) {
+    placemark(clip, out)?;
+  }
+
+  writeln!(out, "  </Document>")?;
+  writeln!(out, "</kml>")?;
+
+  Ok(())
+}
+
+fn placemark(clip: &Clip, out: &mut impl Write) -> Result<()> {
+  writeln!(out, "    <Placemark>")?;
+  writeln!(out, "      <name>{}</name>", escape(&clip.name))?;
+
+  if let Some(note) = &clip.note {
+    writeln!(out, "      <description>{}</description>", escape(note))?;
+  }
+
+  writeln!(out, "      <ExtendedData>")?;
+  data("file", &clip.name, out)?;
+  data("note", clip.note.as_deref().unwrap_or_default(), out)?;
+  data("duration", &clip.seconds.to_string(), out)?;
+  data("status", clip.status().unwrap_or_default(), out)?;
+  writeln!(out, "      </ExtendedData>")?;
+
+  let geometry = if clip.points.len() == 1 { "Point" } else { "LineString" };
+
+  writeln!(out, "      <{}>", geometry)?;
+  writeln!(out, "        <altitudeMode>absolute</altitudeMode>")?;
+  write!(out, "        <coordinates>")?;
+
+  for (i, point) in clip.points.iter().enumerate() {
+    let separator = if i == 0 { "" } else { " " };
+    write!(
+      out,
+      "{}{:.7},{:.7},{:.3}",
+      separator, point.longitude, point.latitude, point.altitude
+    )?;
+  }
+
+  writeln!(out, "</coordinates>")?;
+  writeln!(out, "      </{}>", geometry)?;
+
+  writeln!(out, "    </Placemark>")?;
+
+  Ok(())
+}
+
+fn data(name: &str, value: &str, out: &mut impl Write) -> Result<()> {
+  writeln!(out, r#"        <Data name="{}"><value>{}</value></Data>"#, name, escape(value))?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::{
+    tests::{clip, written},
+    Format,
+  };
+
+  #[test]
+  fn no_fixes() {
+    let kml = written(Format::Kml, vec![clip("GX010123.MP4", 0)]);
+
+    assert!(kml.contains("<name>Mont Blanc</name>"));
+    assert!(!kml.contains("<Placemark>"));
+  }
+
+  #[test]
+  fn single_fix() {
+    let kml = written(Format::Kml, vec![clip("GX010123.MP4", 1)]);
+
+    assert!(kml.contains("<Point>"));
+    assert!(!kml.contains("<LineString>"));
+    assert!(kml.contains("<coordinates>6.0000000,45.0000000,1000.000</coordinates>"));
+  }
+
+  #[test]
+  fn many_fixes() {
+    let kml = written(Format::Kml, vec![clip("GX010123.MP4", 2), clip("GX010124.MP4", 3)]);
+
+    assert_eq!(kml.matches("<LineString>").count(), 2);
+    assert!(kml.contains("<coordinates>6.0000000,45.0000000,1000.000 6.0000000,45.0010000,1001.000</coordinates>"));
+    assert!(kml.contains(r#"<Data name="duration"><value>3</value></Data>"#));
+  }
+}
@@ -0,0 +1,269 @@
+pub mod csv;
+mod geojson;
+mod gpx;
+mod kml;
+
+use std::{
+  fs::{self, File},
+  io::{BufWriter, ErrorKind, Write},
+  path::{Path, PathBuf},
+  sync::mpsc::Sender,
+  thread,
+};
+
+use clap::ArgEnum;
+use itertools::Itertools;
+
+use crate::{
+  error::Result,
+  reader::{
+    mp4::Mp4,
+    track::{self, Point},
+  },
+  ui::{
+    events::Event,
+    state::session::{File as UiFile, Status},
+  },
+  utils,
+};
+
+#[derive(ArgEnum, Clone, Copy, Debug, Default)]
+pub enum Format {
+  #[default]
+  Gpx,
+  #[clap(name = "geojson")]
+  GeoJson,
+  Kml,
+}
+
+impl Format {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Format::Gpx => "gpx",
+      Format::GeoJson => "geojson",
+      Format::Kml => "kml",
+    }
+  }
+
+  pub fn next(&self) -> Self {
+    match self {
+      Format::Gpx => Format::GeoJson,
+      Format::GeoJson => Format::Kml,
+      Format::Kml => Format::Gpx,
+    }
+  }
+}
+
+// The GPS fixes of a single file, along with what's known about it.
+pub struct Clip {
+  pub name: String,
+  pub note: Option<String>,
+  pub seconds: f64,
+  pub status: Option<Status>,
+  pub points: Vec<Point>,
+}
+
+impl Clip {
+  pub fn read(path: &Path) -> Result<Self> {
+    Ok(Self {
+      name: utils::file_name(path)?,
+      note: None,
+      seconds: Mp4::open(path)?.seconds()?,
+      status: None,
+      points: track::read(path)?,
+    })
+  }
+
+  pub fn from_file(file: &UiFile) -> Result<Self> {
+    Ok(Self {
+      name: file.name()?,
+      note: file.note.clone(),
+      seconds: file.seconds,
+      status: file.status.clone(),
+      points: track::read(&file.path)?,
+    })
+  }
+
+  pub fn status(&self) -> Option<&'static str> {
+    match self.status {
+      Some(Status::Import) => Some("import"),
+      Some(Status::Ignore) => Some("ignore"),
+      None => None,
+    }
+  }
+}
+
+// The clips of a file or session, in the order they were recorded.
+pub struct Track {
+  pub name: String,
+  pub clips: Vec<Clip>,
+}
+
+impl Track {
+  pub fn read(name: String, paths: &[PathBuf]) -> Result<Self> {
+    Ok(Self {
+      name,
+      clips: paths.iter().map(|path| Clip::read(path)).collect::<Result<_>>()?,
+    })
+  }
+
+  pub fn from_files(name: String, files: &[UiFile]) -> Result<Self> {
+    Ok(Self {
+      name,
+      clips: files
+        .iter()
+        .flat_map(UiFile::recording)
+        .map(Clip::from_file)
+        .collect::<Result<_>>()?,
+    })
+  }
+
+  // Groups consecutive chapters of the same recording, so they can be joined.
+  pub fn recordings(&self) -> Vec<Vec<&Clip>> {
+    self
+      .clips
+      .iter()
+      .group_by(|clip| utils::chapter(&clip.name).map_or_else(|| clip.name.clone(), |(recording, _)| recording))
+      .into_iter()
+      .map(|(_, clips)| clips.collect())
+      .collect()
+  }
+
+  pub fn write(&self, format: Format, out: &mut impl Write) -> Result<()> {
+    match format {
+      Format::Gpx => gpx::write(self, out),
+      Format::GeoJson => geojson::write(self, out),
+      Format::Kml => kml::write(self, out),
+    }
+  }
+
+  pub fn save(&self, format: Format, path: &Path) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    self.write(format, &mut out)?;
+
+    Ok(out.flush()?)
+  }
+
+  // Saves the track to a new file in `dir` named after it, numbering the name instead of
+  // overwriting an existing file.
+  pub fn save_new(&self, format: Format, dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let name = utils::safe_file_name(&self.name);
+    for number in 0.. {
+      let path = match number {
+        0 => dir.join(format!("{}.{}", name, format.extension())),
+        number => dir.join(format!("{}-{}.{}", name, number, format.extension())),
+      };
+
+      let file = match File::options().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+        Err(error) => return Err(error.into()),
+      };
+
+      let mut out = BufWriter::new(file);
+      self.write(format, &mut out)?;
+      out.flush()?;
+
+      return Ok(path);
+    }
+
+    unreachable!()
+  }
+}
+
+// Exports `files` as a single track to `{name}.{extension}` in `dir`, without blocking the ui.
+pub fn spawn(dir: PathBuf, name: String, files: Vec<UiFile>, format: Format, event_sender: Sender<Event>) {
+  thread::spawn(move || {
+    let event = match Track::from_files(name, &files).and_then(|track| track.save_new(format, &dir)) {
+      Ok(path) => Event::Message(format!("exported {}", path.display())),
+      Err(error) => Event::Error(format!("export: {}", error)),
+    };
+
+    event_sender.send(event).ok();
+  });
+}
+
+// Escapes text for use in xml.
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use super::*;
+
+  // A clip with `fixes` points a second apart, heading north from 45,6.
+  pub fn clip(name: &str, fixes: usize) -> Clip {
+    let points = (0..fixes)
+      .map(|i| Point {
+        latitude: 45.0 + i as f64 * 0.001,
+        longitude: 6.0,
+        altitude: 1000.0 + i as f64,
+        speed_2d: 1.0,
+        time: i as f64,
+        utc: Some(NaiveDate::from_ymd(2023, 4, 5).and_hms_milli(12, 0, i as u32, 500)),
+      })
+      .collect();
+
+    Clip {
+      name: name.to_string(),
+      note: None,
+      seconds: fixes as f64,
+      status: None,
+      points,
+    }
+  }
+
+  pub fn written(format: Format, clips: Vec<Clip>) -> String {
+    let track = Track {
+      name: "Mont Blanc".to_string(),
+      clips,
+    };
+
+    let mut out = Vec::new();
+    track.write(format, &mut out).unwrap();
+
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn recordings() {
+    let track = Track {
+      name: String::new(),
+      clips: vec![
+        clip("GX010123.MP4", 1),
+        clip("GX020123.MP4", 1),
+        clip("GX010124.MP4", 1),
+        clip("clip.mp4", 1),
+      ],
+    };
+
+    let names: Vec<Vec<_>> = track
+      .recordings()
+      .iter()
+      .map(|recording| recording.iter().map(|clip| clip.name.as_str()).collect())
+      .collect();
+
+    assert_eq!(
+      names,
+      vec![vec!["GX010123.MP4", "GX020123.MP4"], vec!["GX010124.MP4"], vec!["clip.mp4"]]
+    );
+  }
+
+  #[test]
+  fn escapes() {
+    assert_eq!(
+      escape(r#"<a href="x">Tom & Jerry's</a>"#),
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+    );
+  }
+}
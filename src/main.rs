@@ -1,8 +1,10 @@
 mod args;
 mod cache;
 mod channel;
+mod commands;
 mod dirs;
 mod error;
+mod export;
 mod mode;
 mod mpv;
 mod reader;
@@ -27,7 +29,11 @@ fn main() -> Result<()> {
 
   let args = Args::parse();
 
-  let mode = Mode::from(args);
+  if let Some(command) = args.command {
+    return commands::run(command);
+  }
+
+  let mode = Mode::from(args)?;
 
   // TODO(enricozb): don't clone the cache; Arc<Mutex<...>> it.
   let cache = SourceCache::from(&mode)?;
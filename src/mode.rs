@@ -1,30 +1,48 @@
 use std::path::PathBuf;
 
-use crate::args::Args;
+use crate::{
+  args::Args,
+  error::{err, Result},
+};
+
+// Sessions are imported into directories named after their date, unless given another template.
+pub const DEFAULT_SESSION_DIR: &str = "{date}";
 
 #[derive(Clone)]
 pub enum Mode {
-  Importing { input_dir: PathBuf, output_dir: PathBuf },
-  Viewing { input_dir: PathBuf },
+  Importing {
+    input_dir: PathBuf,
+    output_dir: PathBuf,
+    session_dir: String,
+    export_dir: Option<PathBuf>,
+  },
+  Viewing {
+    input_dir: PathBuf,
+    export_dir: Option<PathBuf>,
+  },
 }
 
 impl Mode {
-  pub fn from(args: Args) -> Self {
-    match args.input_dir {
+  pub fn from(args: Args) -> Result<Self> {
+    let output_dir = args.output_dir.ok_or(err!("Missing output directory"))?;
+
+    Ok(match args.input_dir {
       Some(input_dir) => Mode::Importing {
         input_dir,
-        output_dir: args.output_dir,
+        output_dir,
+        session_dir: args.session_dir,
+        export_dir: args.export_dir,
       },
-
       None => Mode::Viewing {
-        input_dir: args.output_dir,
+        input_dir: output_dir,
+        export_dir: args.export_dir,
       },
-    }
+    })
   }
 
   pub fn input_dir(&self) -> PathBuf {
     match self {
-      Mode::Importing { input_dir, .. } | Mode::Viewing { input_dir } => input_dir.clone(),
+      Mode::Importing { input_dir, .. } | Mode::Viewing { input_dir, .. } => input_dir.clone(),
     }
   }
 
@@ -34,4 +52,19 @@ impl Mode {
       Mode::Viewing { .. } => None,
     }
   }
+
+  // The directory tracks are exported to, if given.
+  pub fn export_dir(&self) -> Option<PathBuf> {
+    match self {
+      Mode::Importing { export_dir, .. } | Mode::Viewing { export_dir, .. } => export_dir.clone(),
+    }
+  }
+
+  // The template of the names of session directories.
+  pub fn session_dir(&self) -> &str {
+    match self {
+      Mode::Importing { session_dir, .. } => session_dir,
+      Mode::Viewing { .. } => DEFAULT_SESSION_DIR,
+    }
+  }
 }
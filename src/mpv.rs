@@ -3,11 +3,11 @@ use std::{
   process::{Child, Command, Stdio},
 };
 
-use mpvipc::{Mpv, MpvCommand, PlaylistAddOptions};
+use mpvipc::{Mpv, MpvCommand, PlaylistAddOptions, SeekOptions};
 
 use crate::{
   error::{err, Result, WrapErr},
-  ui::state::session::Session,
+  ui::state::session::{File, Session},
 };
 
 const SOCKET: &str = "/tmp/gopro-importer-mpv-socket";
@@ -15,6 +15,9 @@ const SOCKET: &str = "/tmp/gopro-importer-mpv-socket";
 pub struct Player {
   socket: PathBuf,
   process: Child,
+
+  // a seek to `(file_idx, seconds)` waiting for the file to load.
+  pending_seek: Option<(usize, f64)>,
 }
 
 impl Player {
@@ -24,17 +27,52 @@ impl Player {
     Ok(Self {
       process: spawn_mpv_instance(&socket)?,
       socket,
+      pending_seek: None,
     })
   }
 
   pub fn play(&mut self, file_idx: usize) -> Result<()> {
+    self.pending_seek = None;
     self.mpv_connection()?.playlist_play_id(file_idx)?;
 
     Ok(())
   }
 
+  // Plays the file at `file_idx` from `seconds`. mpv only accepts seeks once the file is loaded,
+  // so a file that isn't playing yet is seeked by `sync`.
+  pub fn play_at(&mut self, file_idx: usize, seconds: f64) -> Result<()> {
+    if self.playlist_pos() == Some(file_idx) && self.time_pos().is_some() {
+      self.mpv_connection()?.seek(seconds, SeekOptions::Absolute)?;
+    } else {
+      self.play(file_idx)?;
+      self.pending_seek = Some((file_idx, seconds));
+    }
+
+    Ok(())
+  }
+
+  // Applies a pending seek once its file has loaded.
+  pub fn sync(&mut self) -> Result<()> {
+    let (file_idx, seconds) = match self.pending_seek {
+      Some(pending_seek) => pending_seek,
+      None => return Ok(()),
+    };
+
+    if self.playlist_pos() == Some(file_idx) && self.time_pos().is_some() {
+      self.pending_seek = None;
+      self.mpv_connection()?.seek(seconds, SeekOptions::Absolute)?;
+    }
+
+    Ok(())
+  }
+
+  // The playback position in the current file in seconds.
+  pub fn time_pos(&mut self) -> Option<f64> {
+    self.mpv_connection().ok()?.get_property::<f64>("time-pos").ok()
+  }
+
   pub fn is_playing(&mut self) -> bool {
-    self.playlist_pos() != None
+    self.playlist_pos().is_some()
   }
 
   // TODO(enricozb): should this return a Result?
@@ -52,12 +90,15 @@ impl Player {
 
   // TODO(enricozb): should this return a Result?
   pub fn set_playlist_pos(&mut self, playlist_pos: usize) {
+    self.pending_seek = None;
+
     if let Ok(mpv) = Mpv::connect(SOCKET) {
       mpv.set_property("playlist-pos", playlist_pos).ok();
     }
   }
 
   pub fn load_session(&mut self, session: &Session) -> Result<()> {
+    self.pending_seek = None;
     let mpv = self.mpv_connection()?;
 
     // if the player is currently playing, then the player position is non-negative.
@@ -71,7 +112,7 @@ impl Player {
 
     for (i, file) in session.files.values().enumerate() {
       mpv.run_command(MpvCommand::LoadFile {
-        file: file.path.to_string_lossy().to_string(),
+        file: url(file),
         option: if i == 0 && is_playing {
           PlaylistAddOptions::Replace
         } else {
@@ -96,6 +137,22 @@ impl Player {
   }
 }
 
+// Recordings split into chapters are played as a single file, by listing their chapters in an mpv
+// EDL. Each path is prefixed by its length in bytes so that it may contain any character.
+fn url(file: &File) -> String {
+  if file.chapters.is_empty() {
+    return file.path.to_string_lossy().to_string();
+  }
+
+  let chapters: Vec<_> = file
+    .recording()
+    .map(|chapter| chapter.path.to_string_lossy().to_string())
+    .map(|path| format!("%{}%{}", path.len(), path))
+    .collect();
+
+  format!("edl://{}", chapters.join(";"))
+}
+
 fn spawn_mpv_instance<P: AsRef<Path>>(socket: P) -> Result<Child> {
   Ok(
     Command::new("mpv")
@@ -1,34 +1,87 @@
 use std::path::Path;
 
-use chrono::{
-  offset::{FixedOffset, Utc},
-  DateTime, TimeZone,
-};
+use chrono::{offset::Utc, DateTime, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
 
 use super::{
-  ffmpeg::{self, FFProbeInfo},
-  gpmf::{types::Gps5, Gpmf},
+  gpmf::{
+    types::{Gps5, Gps9},
+    Gpmf,
+  },
+  timezone,
 };
-use crate::error::{err, Result};
+use crate::error::Result;
 
-pub fn approximate(path: &Path, ffprobe_info: &FFProbeInfo) -> Result<DateTime<FixedOffset>> {
-  offset(path, ffprobe_info)?
-    .from_local_datetime(&DateTime::<Utc>::from(path.metadata()?.modified()?).naive_utc())
-    .earliest()
-    .ok_or(err!("Couldn't infer approximate datetime"))
-}
+// Infers when a file was recorded, as a local datetime along with its time zone when GPS data
+// tells it. The UTC time of the first valid fix is used when present, otherwise the file's
+// modified time is assumed to be the camera's local clock, in the zone of the first fix or of
+// the first raw sample when there's no valid fix. GPS9 samples are preferred over GPS5 since
+// they carry their own time.
+//
+// Payloads are only read until the first one with a valid fix, or the first one that can't be read.
+pub fn approximate<I>(path: &Path, payloads: I) -> Result<(NaiveDateTime, Option<Tz>)>
+where
+  I: IntoIterator<Item = Result<Vec<Gpmf>>>,
+{
+  let mut fix = None;
+  let mut raw = None;
+  for payload in payloads {
+    let Ok(payload) = payload else {
+      break;
+    };
+
+    fix = first_gps9(&payload, true)
+      .map(|gps9| (gps9.gps5(), Some(gps9.utc)))
+      .or_else(|| first_gps5(&payload, true));
+    if fix.is_some() {
+      break;
+    }
+
+    raw = raw
+      .or_else(|| first_gps9(&payload, false).map(|gps9| gps9.gps5()))
+      .or_else(|| first_gps5(&payload, false).map(|(gps5, _)| gps5));
+  }
 
-fn offset(path: &Path, ffprobe_info: &FFProbeInfo) -> Result<FixedOffset> {
-  let data = ffmpeg::gpmf_data(path, ffprobe_info)?;
-  let Gps5 { longitude, .. } = first_gps_data(&Gpmf::parse(&data)?).ok_or(err!("No GPS data"))?;
+  let zone = |Gps5 { latitude, longitude, .. }: Gps5| timezone::lookup(latitude, longitude);
 
-  Ok(FixedOffset::east(3600 * (longitude * 12.0 / 180.0) as i32))
+  if let Some((gps5, Some(time))) = fix {
+    let timezone = zone(gps5);
+
+    return Ok((timezone.from_utc_datetime(&time).naive_local(), Some(timezone)));
+  }
+
+  let modified = DateTime::<Utc>::from(path.metadata()?.modified()?).naive_utc();
+
+  Ok((modified, fix.map(|(gps5, _)| gps5).or(raw).map(zone)))
 }
 
-fn first_gps_data(gpmf: &[Gpmf]) -> Option<Gps5> {
+fn first_gps9(gpmf: &[Gpmf], valid: bool) -> Option<Gps9> {
   gpmf.iter().find_map(|g| match g {
-    Gpmf::Klv { entries, .. } => first_gps_data(entries),
-    Gpmf::Gps5 { entries } => entries.get(0).cloned(),
+    Gpmf::Klv { entries, .. } => first_gps9(entries, valid),
+    Gpmf::Gps9 { entries } => entries.iter().find(|gps9| !valid || gps9.is_valid()).cloned(),
+    _ => None,
+  })
+}
+
+// Finds the first GPS5 sample, valid unless `valid` is unset, along with the UTC time of the
+// stream it belongs to.
+fn first_gps5(gpmf: &[Gpmf], valid: bool) -> Option<(Gps5, Option<NaiveDateTime>)> {
+  gpmf.iter().find_map(|g| match g {
+    Gpmf::Klv { key, entries, .. } if key == "STRM" => {
+      let gps5 = entries.iter().find_map(|entry| match entry {
+        Gpmf::Gps5 { entries } => entries.iter().find(|gps5| !valid || gps5.is_valid()).cloned(),
+        _ => None,
+      })?;
+
+      let time = entries.iter().find_map(|entry| match entry {
+        Gpmf::GpsTime { time } => Some(*time),
+        _ => None,
+      });
+
+      Some((gps5, time))
+    }
+
+    Gpmf::Klv { entries, .. } => first_gps5(entries, valid),
     _ => None,
   })
 }
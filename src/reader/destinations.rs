@@ -3,6 +3,7 @@ use std::{path::Path, sync::mpsc::Sender, thread};
 use regex::Regex;
 use walkdir::WalkDir;
 
+use super::places;
 use crate::{
   channel::{EventChannel, ResultChannel},
   error::Result,
@@ -11,21 +12,24 @@ use crate::{
   ui::state::destination::Destination,
 };
 
+const DATE_PATTERN: &str = r"\d{4}-\d{2}-\d{2}";
+
 pub fn spawn(mode: &Mode, event_channel: &EventChannel, result_channel: &ResultChannel) {
   if let Mode::Importing { output_dir, .. } = mode {
     let output_dir = output_dir.clone();
+    let session_dir = mode.session_dir().to_string();
     let event_sender = event_channel.sender();
     let result_sender = result_channel.sender();
 
-    thread::spawn(move || match run(&output_dir, &event_sender) {
+    thread::spawn(move || match run(&output_dir, &session_dir, &event_sender) {
       Ok(_) => (),
       error => result_sender.send(error).unwrap(),
     });
   }
 }
 
-fn run(output_dir: &Path, event_sender: &Sender<Event>) -> Result<()> {
-  let date_re = Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+fn run(output_dir: &Path, session_dir: &str, event_sender: &Sender<Event>) -> Result<()> {
+  let session_re = session_regex(session_dir)?;
 
   let mut it = WalkDir::new(output_dir).into_iter();
 
@@ -41,7 +45,7 @@ fn run(output_dir: &Path, event_sender: &Sender<Event>) -> Result<()> {
     }
 
     if let Some(file_name) = entry.file_name().to_str() {
-      if date_re.is_match(file_name) && entry.file_type().is_dir() {
+      if session_re.is_match(file_name) && entry.file_type().is_dir() {
         event_sender.send(Event::DestinationSession {
           path: entry.path().to_path_buf(),
         })?;
@@ -55,3 +59,14 @@ fn run(output_dir: &Path, event_sender: &Sender<Event>) -> Result<()> {
 
   Ok(())
 }
+
+// Session directories are named after the template of the session directory, or their date as
+// they were before templates and when the template names nothing else.
+fn session_regex(session_dir: &str) -> Result<Regex> {
+  let template = regex::escape(session_dir)
+    .replace(r"\{date\}", DATE_PATTERN)
+    .replace(r"\{place\}", &places::name_pattern())
+    .replace(r"\{country\}", "(?:[A-Z]{2})?");
+
+  Ok(Regex::new(&format!("^(?:{}|{})$", DATE_PATTERN, template))?)
+}
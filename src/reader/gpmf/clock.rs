@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use super::{utils, value::Value, Gpmf};
+
+// Assigns clip-relative times to the samples of consecutive GPMF payloads.
+//
+// Each payload spans a window of the clip, given by the MP4 sample table, but that window doesn't
+// always line up with the time its samples were taken. Streams are anchored to the first payload
+// they appear in, and later payloads are placed relative to it by their STMP, or by TICK on older
+// firmware. The running sample count in TSMP then gives the sample rate of the stream.
+#[derive(Default)]
+pub struct Clock {
+  // the first payload of each stream, by DVID and the key of its samples.
+  anchors: BTreeMap<(String, String), Anchor>,
+}
+
+// The counters of the first payload of a stream, and the start of that payload.
+struct Anchor {
+  start: f64,
+  stmp: Option<f64>,
+  tick: Option<f64>,
+  samples: Option<f64>,
+}
+
+impl Clock {
+  // Times the samples of a payload spanning `duration` seconds from `start`.
+  pub fn time(&mut self, payload: &mut [Gpmf], start: f64, duration: f64) {
+    self.time_device(payload, "", start, duration);
+  }
+
+  // Times a sequence of payloads of a clip lasting `seconds`, assuming every top-level entry
+  // is a payload and that they are evenly spaced.
+  pub fn time_evenly(&mut self, payloads: &mut [Gpmf], seconds: f64) {
+    let duration = seconds / payloads.len() as f64;
+
+    for (i, payload) in payloads.iter_mut().enumerate() {
+      self.time(std::slice::from_mut(payload), i as f64 * duration, duration);
+    }
+  }
+
+  fn time_device(&mut self, entries: &mut [Gpmf], device: &str, start: f64, duration: f64) {
+    for gpmf in entries {
+      match gpmf {
+        Gpmf::Klv { key, entries, .. } if key == "DEVC" => {
+          let device = device_id(entries);
+          self.time_device(entries, &device, start, duration);
+        }
+
+        Gpmf::Klv { key, entries, .. } if key == "STRM" => self.time_stream(entries, device, start, duration),
+        Gpmf::Klv { entries, .. } => self.time_device(entries, device, start, duration),
+        _ => (),
+      }
+    }
+  }
+
+  fn time_stream(&mut self, entries: &mut [Gpmf], device: &str, start: f64, duration: f64) {
+    let stmp = number(entries, "STMP");
+    let tick = number(entries, "TICK");
+    let tock = number(entries, "TOCK");
+    let tsmp = number(entries, "TSMP");
+
+    // the samples of a stream follow its sticky metadata, which isn't timed.
+    let data = match entries.last_mut() {
+      Some(data) if data.sample_count() > 0 => data,
+      _ => return,
+    };
+
+    let count = data.sample_count() as f64;
+    let samples = tsmp.map(|tsmp| tsmp - count);
+
+    let anchor = self.anchors.entry((device.to_string(), data.key().to_string())).or_insert(Anchor {
+      start,
+      stmp,
+      tick,
+      samples,
+    });
+
+    let first = match (stmp, anchor.stmp, tick, anchor.tick) {
+      (Some(stmp), Some(first_stmp), _, _) => anchor.start + (stmp - first_stmp) / 1_000_000.0,
+      (_, _, Some(tick), Some(first_tick)) => anchor.start + (tick - first_tick) / 1000.0,
+      _ => start,
+    };
+
+    // samples delivered since the anchor over the time they took, or this payload's own span.
+    let span = match (samples, anchor.samples, tick, tock) {
+      (Some(samples), Some(first_samples), _, _) if samples > first_samples && first > anchor.start => {
+        count * (first - anchor.start) / (samples - first_samples)
+      }
+
+      (_, _, Some(tick), Some(tock)) if tock > tick => (tock - tick) / 1000.0,
+      _ => duration,
+    };
+
+    data.set_times(first, span);
+  }
+}
+
+fn number(entries: &[Gpmf], key: &str) -> Option<f64> {
+  value(entries, key).and_then(Value::as_f64)
+}
+
+fn value<'a>(entries: &'a [Gpmf], key: &str) -> Option<&'a Value> {
+  entries.iter().find_map(|entry| match entry {
+    Gpmf::Values {
+      key: entry_key, samples, ..
+    } if entry_key == key => utils::first(samples),
+    _ => None,
+  })
+}
+
+// DVID is either a number or a FourCC.
+fn device_id(entries: &[Gpmf]) -> String {
+  match value(entries, "DVID") {
+    Some(Value::FourCC(id) | Value::String(id)) => id.clone(),
+    Some(id) => id.as_f64().map(|id| id.to_string()).unwrap_or_default(),
+    None => String::new(),
+  }
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+
+use nom::{
+  error::{ErrorKind, ParseError as NomParseError},
+  Offset,
+};
+
+pub type IResult<'a, O> = nom::IResult<&'a [u8], O, Error<'a>>;
+
+// A nom error that remembers the keys of the entries it occurred in.
+#[derive(Debug)]
+pub struct Error<'a> {
+  input: &'a [u8],
+  kind: ErrorKind,
+  path: Vec<String>,
+}
+
+impl<'a> NomParseError<&'a [u8]> for Error<'a> {
+  fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+    Self {
+      input,
+      kind,
+      path: Vec::new(),
+    }
+  }
+
+  fn append(_: &'a [u8], _: ErrorKind, other: Self) -> Self {
+    other
+  }
+}
+
+// Records that an error occurred inside the entry `key`.
+pub fn within<'a>(key: &str, error: nom::Err<Error<'a>>) -> nom::Err<Error<'a>> {
+  error.map(|mut error| {
+    error.path.insert(0, key.to_string());
+    error
+  })
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+  // bytes from the start of the parsed data.
+  pub offset: usize,
+
+  // the keys of the entries the error occurred in, outermost first.
+  pub path: Vec<String>,
+
+  pub kind: ErrorKind,
+}
+
+impl ParseError {
+  pub fn new(data: &[u8], error: nom::Err<Error<'_>>) -> Self {
+    match error {
+      nom::Err::Error(error) | nom::Err::Failure(error) => Self {
+        offset: data.offset(error.input),
+        path: error.path,
+        kind: error.kind,
+      },
+
+      nom::Err::Incomplete(_) => Self {
+        offset: data.len(),
+        path: Vec::new(),
+        kind: ErrorKind::Eof,
+      },
+    }
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Failed to parse gpmf data at byte {}", self.offset)?;
+
+    if !self.path.is_empty() {
+      write!(f, " in {}", self.path.join("/"))?;
+    }
+
+    write!(f, ": {}", self.kind.description())
+  }
+}
+
+impl std::error::Error for ParseError {}
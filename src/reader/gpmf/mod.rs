@@ -1,22 +1,29 @@
+pub mod clock;
+pub mod error;
 pub mod types;
 mod utils;
+pub mod value;
 
+use chrono::NaiveDateTime;
+use error::{IResult, ParseError};
 use nom::{
   bytes::complete::take,
-  combinator::all_consuming,
-  multi::many0,
-  number::complete::{be_i16, be_i32, be_u16, be_u32, be_u8},
-  IResult,
+  number::complete::{be_u16, be_u32, be_u8},
 };
 use serde::Serialize;
-use types::Gps5;
+use types::{Fix, Gps5, Gps9, Labels, Orientation, Xyz};
+use value::Value;
 
-use crate::error::{err, Result};
+use crate::error::Result;
 
 #[derive(Serialize)]
 pub enum Gpmf {
   Klv {
     key: String,
+
+    #[serde(skip_serializing_if = "Labels::is_empty")]
+    labels: Labels,
+
     entries: Vec<Self>,
   },
 
@@ -24,8 +31,46 @@ pub enum Gpmf {
     entries: Vec<Gps5>,
   },
 
+  Gps9 {
+    entries: Vec<Gps9>,
+  },
+
+  GpsTime {
+    time: NaiveDateTime,
+  },
+
+  GpsFix {
+    fix: Fix,
+  },
+
+  GpsPrecision {
+    dop: f64,
+  },
+
+  Accl {
+    entries: Vec<Xyz>,
+  },
+
+  Gyro {
+    entries: Vec<Xyz>,
+  },
+
+  Magn {
+    entries: Vec<Xyz>,
+  },
+
   Scale {
-    divisors: Vec<i64>,
+    divisors: Vec<f64>,
+  },
+
+  Values {
+    key: String,
+    kind: char,
+    samples: Vec<Vec<Value>>,
+
+    // seconds since the start of the clip, of each sample.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    times: Vec<f64>,
   },
 
   Unknown {
@@ -40,10 +85,15 @@ pub enum Gpmf {
 }
 
 impl Gpmf {
-  fn parse_one(input: &[u8]) -> IResult<&[u8], Self> {
+  fn parse_one(input: &[u8]) -> IResult<'_, Self> {
     let (input, key) = be_u32(input)?;
     let key = Self::key_string(key);
+    let path = key.clone();
+
+    Self::parse_entry(key, input).map_err(|e| error::within(&path, e))
+  }
 
+  fn parse_entry(key: String, input: &[u8]) -> IResult<'_, Self> {
     let (input, kind) = be_u8(input)?;
     let (input, size) = be_u8(input)?;
     let (input, count) = be_u16(input)?;
@@ -53,26 +103,51 @@ impl Gpmf {
     let (input, data) = take(data_size)(input)?;
 
     let gpmf = match (key.as_str(), kind) {
-      ("DEVC", kind::NULL) => Self::Klv {
-        key,
-        entries: Self::parse_many(data)?.1,
-      },
+      ("STRM", kind::NULL) => {
+        let entries = Self::parse_strm(data)?.1;
+
+        Self::Klv {
+          key,
+          labels: Labels::from_entries(&entries),
+          entries,
+        }
+      }
 
-      ("STRM", kind::NULL) => Self::Klv {
+      ("DEVC", kind::NULL) => {
+        let mut entries = Self::parse_many(data)?.1;
+        let labels = Labels::from_entries(&entries);
+
+        // every stream of a device is labelled with the device's name.
+        for entry in &mut entries {
+          if let Self::Klv { key, labels: stream, .. } = entry {
+            if key == "STRM" {
+              stream.device = labels.device.clone();
+            }
+          }
+        }
+
+        Self::Klv { key, labels, entries }
+      }
+
+      (_, kind::NULL) => Self::Klv {
         key,
-        entries: Self::parse_strm(data)?.1,
+        labels: Labels::default(),
+        entries: Self::parse_many(data)?.1,
       },
 
-      ("SCAL", kind::I32) => Self::Scale {
-        divisors: all_consuming(many0(be_i32))(data)?.1.into_iter().map(i64::from).collect(),
-      },
+      ("SCAL", kind) if Value::is_known(kind) => {
+        let samples = Value::parse_samples(kind, size as usize, count as usize, data)?.1;
 
-      ("SCAL", kind::I16) => Self::Scale {
-        divisors: all_consuming(many0(be_i16))(data)?.1.into_iter().map(i64::from).collect(),
-      },
+        Self::Scale {
+          divisors: samples.iter().flat_map(|sample| sample.iter().filter_map(Value::as_f64)).collect(),
+        }
+      }
 
-      ("GPS5", kind::I32) => Self::Gps5 {
-        entries: Gps5::parse_many(data)?.1,
+      (_, kind) if Value::is_known(kind) => Self::Values {
+        key,
+        kind: kind as char,
+        samples: Value::parse_samples(kind, size as usize, count as usize, data)?.1,
+        times: Vec::new(),
       },
 
       _ => Self::Unknown {
@@ -87,37 +162,74 @@ impl Gpmf {
     Ok((input, gpmf))
   }
 
-  fn parse_many(input: &[u8]) -> IResult<&[u8], Vec<Self>> {
-    all_consuming(many0(Self::parse_one))(input)
+  fn parse_many(mut input: &[u8]) -> IResult<'_, Vec<Self>> {
+    let mut klvs = Vec::new();
+
+    while !input.is_empty() {
+      let (rest, gpmf) = Self::parse_one(input)?;
+      klvs.push(gpmf);
+
+      input = rest;
+    }
+
+    Ok((input, klvs))
   }
 
-  fn parse_strm(mut input: &[u8]) -> IResult<&[u8], Vec<Self>> {
-    let mut divisors = Vec::new();
+  // Parses the entries of a STRM, applying sticky metadata (such as SCAL and ORIN) to the
+  // entries that follow it, and converting known keys into their typed variants.
+  fn parse_strm(mut input: &[u8]) -> IResult<'_, Vec<Self>> {
+    let mut sticky = Sticky::default();
     let mut klvs = Vec::new();
 
     while !input.is_empty() {
       let (rest, gpmf) = Self::parse_one(input)?;
 
-      let gpmf = match &gpmf {
-        Self::Scale { divisors: divs } => {
-          divisors = divs.clone();
-          gpmf
-        }
+      let gpmf = match gpmf {
+        // complex samples can only be decoded once the TYPE describing them is known.
+        Self::Unknown {
+          key,
+          kind,
+          size,
+          count,
+          data,
+        } if kind as u8 == kind::COMPLEX => match sticky.types(&key) {
+          Some(types) => {
+            // parsed from the input rather than `data`, so that errors point into the input.
+            let data = &input[HEADER_SIZE..];
+            let samples = Value::parse_structs(&types, size as usize, count as usize, data)
+              .map_err(|e| error::within(&key, e))?
+              .1;
+            let samples = Self::scale(samples, &sticky.divisors);
 
-        Self::Gps5 { entries } => Self::Gps5 {
-          entries: entries
-            .iter()
-            .map(|g| Gps5 {
-              latitude: g.latitude / *divisors.get(0).unwrap_or(&1) as f64,
-              longitude: g.longitude / *divisors.get(1).unwrap_or(&1) as f64,
-              altitude: g.altitude / *divisors.get(2).unwrap_or(&1) as f64,
-              speed_2d: g.speed_2d / *divisors.get(3).unwrap_or(&1) as f64,
-              speed_3d: g.speed_3d / *divisors.get(4).unwrap_or(&1) as f64,
-            })
-            .collect(),
+            Self::typed(key, kind, samples, &sticky)
+          }
+
+          None => Self::Unknown {
+            key,
+            kind,
+            size,
+            count,
+            data,
+          },
         },
 
-        _ => gpmf,
+        Self::Scale { divisors } => {
+          sticky.divisors = divisors.clone();
+          Self::Scale { divisors }
+        }
+
+        Self::Values { key, kind, samples, .. } => {
+          let samples = if UNSCALED.contains(&key.as_str()) {
+            samples
+          } else {
+            Self::scale(samples, &sticky.divisors)
+          };
+
+          sticky.update(&key, &samples);
+          Self::typed(key, kind, samples, &sticky)
+        }
+
+        gpmf => gpmf,
       };
 
       klvs.push(gpmf);
@@ -128,21 +240,393 @@ impl Gpmf {
     Ok((input, klvs))
   }
 
+  // Divides each element of each sample by its `SCAL` divisor. A single divisor applies
+  // to every element, otherwise divisors apply positionally.
+  fn scale(samples: Vec<Vec<Value>>, divisors: &[f64]) -> Vec<Vec<Value>> {
+    if divisors.is_empty() {
+      return samples;
+    }
+
+    samples
+      .into_iter()
+      .map(|sample| {
+        sample
+          .iter()
+          .enumerate()
+          .map(|(i, value)| {
+            let divisor = if divisors.len() == 1 {
+              divisors[0]
+            } else {
+              *divisors.get(i).unwrap_or(&1.0)
+            };
+            value.scaled(divisor)
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  fn typed(key: String, kind: char, samples: Vec<Vec<Value>>, sticky: &Sticky) -> Self {
+    let typed = match key.as_str() {
+      "GPS5" => Gps5::from_samples(&samples, sticky.fix, sticky.dop).map(|entries| Self::Gps5 { entries }),
+      "GPS9" => Gps9::from_samples(&samples).map(|entries| Self::Gps9 { entries }),
+      "GPSF" => sticky.fix.map(|fix| Self::GpsFix { fix }),
+      "GPSP" => sticky.dop.map(|dop| Self::GpsPrecision { dop }),
+      "GPSU" => match utils::first(&samples) {
+        Some(Value::DateTime(time)) => Some(Self::GpsTime { time: *time }),
+        _ => None,
+      },
+      "ACCL" => sticky.xyz(&samples).map(|entries| Self::Accl { entries }),
+      "GYRO" => sticky.xyz(&samples).map(|entries| Self::Gyro { entries }),
+      "MAGN" => sticky.xyz(&samples).map(|entries| Self::Magn { entries }),
+      _ => None,
+    };
+
+    typed.unwrap_or(Self::Values {
+      key,
+      kind,
+      samples,
+      times: Vec::new(),
+    })
+  }
+
+  pub fn key(&self) -> &str {
+    match self {
+      Self::Klv { key, .. } | Self::Values { key, .. } | Self::Unknown { key, .. } => key,
+      Self::Gps5 { .. } => "GPS5",
+      Self::Gps9 { .. } => "GPS9",
+      Self::GpsTime { .. } => "GPSU",
+      Self::GpsFix { .. } => "GPSF",
+      Self::GpsPrecision { .. } => "GPSP",
+      Self::Accl { .. } => "ACCL",
+      Self::Gyro { .. } => "GYRO",
+      Self::Magn { .. } => "MAGN",
+      Self::Scale { .. } => "SCAL",
+    }
+  }
+
+  // The number of samples that `set_times` spreads out, zero for entries that aren't sampled.
+  fn sample_count(&self) -> usize {
+    match self {
+      Self::Gps5 { entries } => entries.len(),
+      Self::Gps9 { entries } => entries.len(),
+      Self::Accl { entries } | Self::Gyro { entries } | Self::Magn { entries } => entries.len(),
+      Self::Values { samples, .. } => samples.len(),
+      _ => 0,
+    }
+  }
+
+  // Spreads the samples of this entry evenly over `duration` seconds from `start`.
+  fn set_times(&mut self, start: f64, duration: f64) {
+    let time = |i: usize, n: usize| start + duration * i as f64 / n as f64;
+
+    match self {
+      Self::Gps5 { entries } => {
+        let n = entries.len();
+        for (i, gps5) in entries.iter_mut().enumerate() {
+          gps5.time = time(i, n);
+        }
+      }
+
+      Self::Gps9 { entries } => {
+        let n = entries.len();
+        for (i, gps9) in entries.iter_mut().enumerate() {
+          gps9.time = time(i, n);
+        }
+      }
+
+      Self::Accl { entries } | Self::Gyro { entries } | Self::Magn { entries } => {
+        let n = entries.len();
+        for (i, xyz) in entries.iter_mut().enumerate() {
+          xyz.time = time(i, n);
+        }
+      }
+
+      Self::Values { samples, times, .. } => *times = (0..samples.len()).map(|i| time(i, samples.len())).collect(),
+
+      _ => (),
+    }
+  }
+
   fn key_string(key: u32) -> String {
     key.to_be_bytes().into_iter().map(|b| b as char).collect()
   }
 
   pub fn parse(input: &[u8]) -> Result<Vec<Self>> {
     match Self::parse_many(input) {
-      Ok(([], entries)) => Ok(entries),
-      Ok(_) => unreachable!(),
-      Err(error) => Err(err!("Failed to parse gpmf data: {}", error)),
+      Ok((_, entries)) => Ok(entries),
+      Err(error) => Err(ParseError::new(input, error).into()),
+    }
+  }
+
+  // Parses every top-level entry that can be parsed, skipping damaged ones. Parsing resumes at
+  // the next DEVC after a damaged entry, since its size can't be trusted.
+  pub fn parse_recovering(input: &[u8]) -> (Vec<Self>, Vec<ParseError>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+      match Self::parse_one(rest) {
+        Ok((next, entry)) => {
+          entries.push(entry);
+          rest = next;
+        }
+
+        Err(error) => {
+          errors.push(ParseError::new(input, error));
+
+          // entries are always 4-byte aligned.
+          rest = match (4..rest.len()).step_by(4).find(|i| rest[*i..].starts_with(b"DEVC")) {
+            Some(i) => &rest[i..],
+            None => &[],
+          };
+        }
+      }
+    }
+
+    (entries, errors)
+  }
+}
+
+// The size of the key, type, size and count of an entry.
+const HEADER_SIZE: usize = 8;
+
+// Keys whose values are never divided by SCAL, even when they follow it.
+const UNSCALED: [&str; 7] = ["STMP", "TSMP", "TICK", "TOCK", "ORIN", "GPSF", "GPSP"];
+
+// Metadata that applies to every following entry of the same STRM.
+#[derive(Default)]
+struct Sticky {
+  divisors: Vec<f64>,
+  // streams without ORIN keep their samples unlabeled, as the channel order differs between models.
+  orientation: Option<Orientation>,
+  fix: Option<Fix>,
+  dop: Option<f64>,
+  types: Option<Vec<(u8, usize)>>,
+}
+
+impl Sticky {
+  fn update(&mut self, key: &str, samples: &[Vec<Value>]) {
+    let value = utils::first(samples);
+
+    match key {
+      "ORIN" => {
+        if let Some(orientation) = value.and_then(Value::as_str).and_then(Orientation::parse) {
+          self.orientation = Some(orientation);
+        }
+      }
+
+      "TYPE" => self.types = value.and_then(Value::as_str).and_then(Value::struct_kinds),
+
+      "GPSF" => self.fix = value.and_then(Value::as_f64).map(Fix::from),
+
+      // GPSP is the dilution of precision multiplied by 100.
+      "GPSP" => self.dop = value.and_then(Value::as_f64).map(|dop| dop / 100.0),
+
+      _ => (),
+    }
+  }
+
+  fn xyz(&self, samples: &[Vec<Value>]) -> Option<Vec<Xyz>> {
+    Xyz::from_samples(samples, self.orientation.as_ref()?)
+  }
+
+  // The field types of the complex samples of `key`, falling back to the known layout of
+  // GPS9 for streams that don't describe it.
+  fn types(&self, key: &str) -> Option<Vec<(u8, usize)>> {
+    match (&self.types, key) {
+      (Some(types), _) => Some(types.clone()),
+      (None, "GPS9") => Some(Gps9::TYPE.iter().map(|kind| (*kind, 1)).collect()),
+      (None, _) => None,
     }
   }
 }
 
 mod kind {
   pub const NULL: u8 = b'\x00';
-  pub const I16: u8 = b's';
+  pub const I8: u8 = b'b';
+  pub const U8: u8 = b'B';
+  pub const STRING: u8 = b'c';
+  pub const UTF8: u8 = b'u';
+  pub const F64: u8 = b'd';
+  pub const F32: u8 = b'f';
+  pub const FOURCC: u8 = b'F';
+  pub const UUID: u8 = b'G';
+  pub const I64: u8 = b'j';
+  pub const U64: u8 = b'J';
   pub const I32: u8 = b'l';
+  pub const U32: u8 = b'L';
+  pub const FIXED32: u8 = b'q';
+  pub const FIXED64: u8 = b'Q';
+  pub const I16: u8 = b's';
+  pub const U16: u8 = b'S';
+  pub const DATETIME: u8 = b'U';
+  pub const COMPLEX: u8 = b'?';
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn klv(key: &[u8; 4], kind: u8, size: u8, count: u16, data: &[u8]) -> Vec<u8> {
+    let mut klv = [key.as_slice(), &[kind, size], &count.to_be_bytes(), data].concat();
+    klv.resize(HEADER_SIZE + utils::align(data.len()), 0);
+
+    klv
+  }
+
+  fn nested(key: &[u8; 4], entries: &[Vec<u8>]) -> Vec<u8> {
+    let data = entries.concat();
+
+    klv(key, kind::NULL, 4, (data.len() / 4) as u16, &data)
+  }
+
+  fn i32s(values: &[i32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_be_bytes()).collect()
+  }
+
+  // The entries of the single STRM of the single DEVC in `input`.
+  fn stream(input: &[u8]) -> Vec<Gpmf> {
+    match Gpmf::parse(input).unwrap().pop() {
+      Some(Gpmf::Klv { mut entries, .. }) => match entries.pop() {
+        Some(Gpmf::Klv { key, entries, .. }) if key == "STRM" => entries,
+        _ => panic!("no STRM"),
+      },
+      _ => panic!("no DEVC"),
+    }
+  }
+
+  #[test]
+  fn gps5_with_sticky_metadata() {
+    let strm = nested(
+      b"STRM",
+      &[
+        klv(b"GPSF", kind::U32, 4, 1, &3_u32.to_be_bytes()),
+        klv(b"GPSP", kind::U16, 2, 1, &150_u16.to_be_bytes()),
+        klv(b"SCAL", kind::I32, 4, 5, &i32s(&[10_000_000, 10_000_000, 1000, 1000, 100])),
+        klv(b"GPS5", kind::I32, 20, 1, &i32s(&[459_237_000, 68_694_000, 1_035_000, 1500, 150])),
+      ],
+    );
+
+    let entries = stream(&nested(b"DEVC", &[strm]));
+    let gps5 = entries.iter().find_map(|entry| match entry {
+      Gpmf::Gps5 { entries } => entries.first(),
+      _ => None,
+    });
+
+    let gps5 = gps5.unwrap();
+    assert_eq!((gps5.latitude, gps5.longitude, gps5.altitude), (45.9237, 6.8694, 1035.0));
+    assert_eq!((gps5.speed_2d, gps5.speed_3d), (1.5, 1.5));
+    assert!(gps5.fix == Some(Fix::ThreeD));
+    assert_eq!(gps5.dop, Some(1.5));
+    assert!(gps5.is_valid());
+  }
+
+  #[test]
+  fn orientation() {
+    let accl = |orin: Option<&[u8]>| {
+      let mut entries = Vec::new();
+      if let Some(orin) = orin {
+        entries.push(klv(b"ORIN", kind::STRING, 1, 3, orin));
+      }
+      entries.push(klv(b"SCAL", kind::I16, 2, 1, &10_i16.to_be_bytes()));
+      entries.push(klv(b"ACCL", kind::I16, 6, 1, &[0, 10, 0, 20, 0xff, 0xe2]));
+
+      stream(&nested(b"DEVC", &[nested(b"STRM", &entries)])).pop().unwrap()
+    };
+
+    match accl(Some(b"ZxY")) {
+      Gpmf::Accl { entries } => assert_eq!((entries[0].x, entries[0].y, entries[0].z), (-2.0, -3.0, 1.0)),
+      _ => panic!("ACCL with ORIN isn't labeled"),
+    }
+
+    // the channel order of streams without ORIN isn't known.
+    match accl(None) {
+      Gpmf::Values { key, samples, .. } => {
+        assert_eq!(key, "ACCL");
+        assert_eq!(samples, vec![vec![Value::F64(1.0), Value::F64(2.0), Value::F64(-3.0)]]);
+      }
+      _ => panic!("ACCL without ORIN is labeled"),
+    }
+  }
+
+  #[test]
+  fn complex_samples() {
+    let strm = nested(
+      b"STRM",
+      &[
+        klv(b"TYPE", kind::STRING, 1, 2, b"lS"),
+        klv(b"ABCD", kind::COMPLEX, 6, 2, &[0xff, 0xff, 0xff, 0xfe, 0, 7, 0, 0, 0, 1, 0, 2]),
+      ],
+    );
+
+    match stream(&nested(b"DEVC", &[strm])).pop() {
+      Some(Gpmf::Values { key, samples, .. }) => {
+        assert_eq!(key, "ABCD");
+        assert_eq!(
+          samples,
+          vec![vec![Value::I32(-2), Value::U16(7)], vec![Value::I32(1), Value::U16(2)]]
+        );
+      }
+      _ => panic!("complex samples weren't decoded"),
+    }
+  }
+
+  #[test]
+  fn gps9_without_type() {
+    // 2023-04-05 is 8495 days after 2000-01-01.
+    let sample = [
+      i32s(&[459_237_000, 68_694_000, 1_035_000, 1500, 150, 8495, 45_296_500]),
+      [0, 150, 0, 3].to_vec(),
+    ]
+    .concat();
+    let strm = nested(
+      b"STRM",
+      &[
+        klv(
+          b"SCAL",
+          kind::I32,
+          4,
+          9,
+          &i32s(&[10_000_000, 10_000_000, 1000, 1000, 100, 1, 1000, 100, 1]),
+        ),
+        klv(b"GPS9", kind::COMPLEX, 32, 1, &sample),
+      ],
+    );
+
+    match stream(&nested(b"DEVC", &[strm])).pop() {
+      Some(Gpmf::Gps9 { entries }) => {
+        assert_eq!((entries[0].latitude, entries[0].longitude), (45.9237, 6.8694));
+        assert_eq!(entries[0].utc.to_string(), "2023-04-05 12:34:56.500");
+        assert_eq!(entries[0].dop, 1.5);
+        assert!(entries[0].fix == Fix::ThreeD);
+      }
+      _ => panic!("GPS9 wasn't decoded"),
+    }
+  }
+
+  #[test]
+  fn truncated_entry() {
+    let mut devc = nested(
+      b"DEVC",
+      &[nested(b"STRM", &[klv(b"GPS5", kind::I32, 20, 1, &i32s(&[1, 2, 3, 4, 5]))])],
+    );
+    devc.truncate(devc.len() - 4);
+
+    assert!(Gpmf::parse(&devc).is_err());
+  }
+
+  #[test]
+  fn recovering_skips_damaged_devc() {
+    let good = nested(b"DEVC", &[nested(b"STRM", &[klv(b"STNM", kind::STRING, 1, 4, b"GPS ")])]);
+
+    // claims a GPS5 sample of 20 bytes, but only has 12.
+    let damaged = nested(b"DEVC", &[[b"GPS5".as_slice(), &[kind::I32, 20, 0, 1], &i32s(&[1, 2, 3])].concat()]);
+
+    let (entries, errors) = Gpmf::parse_recovering(&[damaged, good].concat());
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(errors.len(), 1);
+  }
 }
@@ -1,11 +1,8 @@
-use nom::{
-  combinator::all_consuming,
-  multi::{many0, many_m_n},
-  number::complete::be_i32,
-  IResult,
-};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use serde::Serialize;
 
+use super::{utils, value::Value, Gpmf};
+
 #[derive(Serialize, Clone)]
 pub struct Gps5 {
   pub latitude: f64,
@@ -13,28 +10,248 @@ pub struct Gps5 {
   pub altitude: f64,
   pub speed_2d: f64,
   pub speed_3d: f64,
+
+  pub fix: Option<Fix>,
+  pub dop: Option<f64>,
+
+  // seconds since the start of the clip.
+  pub time: f64,
 }
 
 impl Gps5 {
-  pub fn parse_one(input: &[u8]) -> IResult<&[u8], Self> {
-    let (input, parsed) = many_m_n(5, 5, be_i32)(input)?;
-
-    let gps5 = match parsed.as_slice() {
-      [latitude, longitude, altitude, speed_2d, speed_3d] => Gps5 {
-        latitude: f64::from(*latitude),
-        longitude: f64::from(*longitude),
-        altitude: f64::from(*altitude),
-        speed_2d: f64::from(*speed_2d),
-        speed_3d: f64::from(*speed_3d),
-      },
-
-      _ => unreachable!(),
+  // Dilution of precision above which a fix is too imprecise to be used.
+  const MAX_DOP: f64 = 5.0;
+
+  pub fn from_sample(sample: &[Value], fix: Option<Fix>, dop: Option<f64>) -> Option<Self> {
+    match utils::numbers(sample)?.as_slice() {
+      [latitude, longitude, altitude, speed_2d, speed_3d] => Some(Gps5 {
+        latitude: *latitude,
+        longitude: *longitude,
+        altitude: *altitude,
+        speed_2d: *speed_2d,
+        speed_3d: *speed_3d,
+
+        fix,
+        dop,
+
+        time: 0.0,
+      }),
+
+      _ => None,
+    }
+  }
+
+  pub fn from_samples(samples: &[Vec<Value>], fix: Option<Fix>, dop: Option<f64>) -> Option<Vec<Self>> {
+    samples.iter().map(|sample| Self::from_sample(sample, fix, dop)).collect()
+  }
+
+  // Whether this sample had a 2D or 3D lock with acceptable precision. Samples from
+  // streams without GPSF or GPSP are only rejected if they are exactly at 0,0.
+  pub fn is_valid(&self) -> bool {
+    let has_fix = self.fix != Some(Fix::None);
+    let is_precise = self.dop.is_none_or(|dop| dop <= Self::MAX_DOP);
+    let is_null_island = self.latitude == 0.0 && self.longitude == 0.0;
+
+    has_fix && is_precise && !is_null_island
+  }
+}
+
+// A GPS sample from newer cameras, which carries its own UTC time, precision and fix.
+#[derive(Serialize, Clone)]
+pub struct Gps9 {
+  pub latitude: f64,
+  pub longitude: f64,
+  pub altitude: f64,
+  pub speed_2d: f64,
+  pub speed_3d: f64,
+  pub utc: NaiveDateTime,
+  pub dop: f64,
+  pub fix: Fix,
+
+  // seconds since the start of the clip.
+  pub time: f64,
+}
+
+impl Gps9 {
+  // The structure of a GPS9 sample, as described by its TYPE key.
+  pub const TYPE: &'static [u8] = b"lllllllSS";
+
+  pub fn from_sample(sample: &[Value]) -> Option<Self> {
+    match utils::numbers(sample)?.as_slice() {
+      [latitude, longitude, altitude, speed_2d, speed_3d, days, seconds, dop, fix] => Some(Gps9 {
+        latitude: *latitude,
+        longitude: *longitude,
+        altitude: *altitude,
+        speed_2d: *speed_2d,
+        speed_3d: *speed_3d,
+        utc: Self::utc(*days, *seconds),
+        dop: *dop,
+        fix: Fix::from(*fix),
+
+        time: 0.0,
+      }),
+
+      _ => None,
+    }
+  }
+
+  pub fn from_samples(samples: &[Vec<Value>]) -> Option<Vec<Self>> {
+    samples.iter().map(|sample| Self::from_sample(sample)).collect()
+  }
+
+  pub fn gps5(&self) -> Gps5 {
+    Gps5 {
+      latitude: self.latitude,
+      longitude: self.longitude,
+      altitude: self.altitude,
+      speed_2d: self.speed_2d,
+      speed_3d: self.speed_3d,
+
+      fix: Some(self.fix),
+      dop: Some(self.dop),
+
+      time: self.time,
+    }
+  }
+
+  pub fn is_valid(&self) -> bool {
+    self.gps5().is_valid()
+  }
+
+  // GPS9 times are days since 2000-01-01 and seconds since midnight.
+  fn utc(days: f64, seconds: f64) -> NaiveDateTime {
+    NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0)
+      + Duration::days(days as i64)
+      + Duration::milliseconds((seconds * 1000.0).round() as i64)
+  }
+}
+
+// The GPS lock reported by GPSF.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Fix {
+  None,
+  TwoD,
+  ThreeD,
+}
+
+impl From<f64> for Fix {
+  fn from(gpsf: f64) -> Self {
+    match gpsf as u32 {
+      2 => Self::TwoD,
+      3 => Self::ThreeD,
+      _ => Self::None,
+    }
+  }
+}
+
+// A three-axis sample from the accelerometer, gyroscope or magnetometer, in camera axes.
+#[derive(Serialize, Clone)]
+pub struct Xyz {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+
+  // seconds since the start of the clip.
+  pub time: f64,
+}
+
+impl Xyz {
+  pub fn from_sample(sample: &[Value], orientation: &Orientation) -> Option<Self> {
+    let numbers = utils::numbers(sample)?;
+    if numbers.len() != 3 {
+      return None;
+    }
+
+    let mut axes = [0.0; 3];
+    for (value, (axis, sign)) in numbers.into_iter().zip(orientation.channels) {
+      axes[axis] = value * sign;
+    }
+
+    Some(Self {
+      x: axes[0],
+      y: axes[1],
+      z: axes[2],
+
+      time: 0.0,
+    })
+  }
+
+  pub fn from_samples(samples: &[Vec<Value>], orientation: &Orientation) -> Option<Vec<Self>> {
+    samples.iter().map(|sample| Self::from_sample(sample, orientation)).collect()
+  }
+}
+
+// The input channel order described by ORIN, e.g. "ZXY" or "YxZ".
+//
+// Each channel maps to a camera axis, a lowercase letter means the axis is inverted.
+#[derive(Clone)]
+pub struct Orientation {
+  channels: [(usize, f64); 3],
+}
+
+impl Orientation {
+  pub fn parse(orin: &str) -> Option<Self> {
+    let mut channels = [(0, 1.0); 3];
+
+    if orin.chars().count() != 3 {
+      return None;
+    }
+
+    for (channel, c) in channels.iter_mut().zip(orin.chars()) {
+      let axis = match c.to_ascii_uppercase() {
+        'X' => 0,
+        'Y' => 1,
+        'Z' => 2,
+        _ => return None,
+      };
+
+      *channel = (axis, if c.is_ascii_lowercase() { -1.0 } else { 1.0 });
+    }
+
+    Some(Self { channels })
+  }
+}
+
+// Human-readable labels of a DEVC or STRM, from DVNM, STNM, SIUN and UNIT.
+#[derive(Serialize, Clone, Default)]
+pub struct Labels {
+  // the name of the device the stream was recorded by, e.g. "Camera".
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub device: Option<String>,
+
+  // the name of the stream, e.g. "Accelerometer".
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+
+  // the unit of each element of a sample, or a single unit for all of them. SI units are
+  // preferred over display units.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub units: Vec<String>,
+}
+
+impl Labels {
+  pub fn from_entries(entries: &[Gpmf]) -> Self {
+    let strings = |key: &str| -> Vec<String> {
+      entries
+        .iter()
+        .filter_map(|entry| match entry {
+          Gpmf::Values { key: k, samples, .. } if k == key => Some(samples),
+          _ => None,
+        })
+        .flat_map(|samples| samples.iter().flatten().filter_map(Value::as_str).map(str::to_string))
+        .collect()
     };
 
-    Ok((input, gps5))
+    let si_units = strings("SIUN");
+
+    Self {
+      device: strings("DVNM").into_iter().next(),
+      name: strings("STNM").into_iter().next(),
+      units: if si_units.is_empty() { strings("UNIT") } else { si_units },
+    }
   }
 
-  pub fn parse_many(input: &[u8]) -> IResult<&[u8], Vec<Self>> {
-    all_consuming(many0(Self::parse_one))(input)
+  pub fn is_empty(&self) -> bool {
+    self.device.is_none() && self.name.is_none() && self.units.is_empty()
   }
 }
@@ -1,3 +1,5 @@
+use super::value::Value;
+
 pub fn align(count: usize) -> usize {
   let rem = count % 4;
   if rem == 0 {
@@ -6,3 +8,11 @@ pub fn align(count: usize) -> usize {
     count + 4 - rem
   }
 }
+
+pub fn numbers(sample: &[Value]) -> Option<Vec<f64>> {
+  sample.iter().map(Value::as_f64).collect()
+}
+
+pub fn first(samples: &[Vec<Value>]) -> Option<&Value> {
+  samples.first().and_then(|sample| sample.first())
+}
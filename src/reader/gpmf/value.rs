@@ -0,0 +1,371 @@
+use chrono::NaiveDateTime;
+use nom::{
+  bytes::complete::take,
+  combinator::{all_consuming, fail, map},
+  multi::many0,
+  number::complete::{be_f32, be_f64, be_i16, be_i32, be_i64, be_i8, be_u16, be_u32, be_u64, be_u8},
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{error::IResult, kind};
+
+// A single decoded element of a GPMF sample.
+//
+// Numeric values are converted to `F64` when a `SCAL` divisor is applied to them,
+// every other variant is kept as-is.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Value {
+  I8(i8),
+  U8(u8),
+  I16(i16),
+  U16(u16),
+  I32(i32),
+  U32(u32),
+  I64(i64),
+  U64(u64),
+  F32(f32),
+  F64(f64),
+  Fixed32(f64),
+  Fixed64(f64),
+  String(String),
+  FourCC(String),
+  Uuid(Uuid),
+  DateTime(NaiveDateTime),
+}
+
+impl Value {
+  // Parses `count` samples of `size` bytes each, where every sample holds as many elements
+  // of `kind` as fit in `size`. Strings and dates occupy a whole sample.
+  pub fn parse_samples(kind: u8, size: usize, count: usize, input: &[u8]) -> IResult<'_, Vec<Vec<Self>>> {
+    let (input, data) = take(size * count)(input)?;
+
+    if size == 0 {
+      return Ok((input, Vec::new()));
+    }
+
+    // strings are frequently stored as `count` single-character samples.
+    if Self::is_text(kind) && size == 1 {
+      return Ok((input, vec![vec![Self::String(Self::text(kind, data))]]));
+    }
+
+    let mut samples = Vec::with_capacity(count);
+    for sample in data.chunks(size) {
+      samples.push(Self::parse_sample(kind, sample)?.1);
+    }
+
+    Ok((input, samples))
+  }
+
+  // Parses `count` samples of `size` bytes each, where every sample is a structure with the
+  // fields of `types`, as returned by `struct_kinds`.
+  pub fn parse_structs<'a>(types: &[(u8, usize)], size: usize, count: usize, input: &'a [u8]) -> IResult<'a, Vec<Vec<Self>>> {
+    // a TYPE describing more bytes than a sample holds can't be trusted.
+    let struct_size = types.iter().try_fold(0_usize, |total, (kind, repeat)| {
+      total.checked_add(Self::size(*kind)?.checked_mul(*repeat)?)
+    });
+    if struct_size.is_none_or(|struct_size| struct_size > size) {
+      return fail(input);
+    }
+
+    let (input, data) = take(size * count)(input)?;
+
+    if size == 0 {
+      return Ok((input, Vec::new()));
+    }
+
+    let mut samples = Vec::with_capacity(count);
+    for mut sample in data.chunks(size) {
+      let mut fields = Vec::with_capacity(types.len());
+
+      for &(kind, repeat) in types {
+        // a repeated string type is a single string of that many characters.
+        if Self::is_text(kind) {
+          let (rest, text) = take(repeat)(sample)?;
+          fields.push(Self::String(Self::text(kind, text)));
+          sample = rest;
+          continue;
+        }
+
+        for _ in 0..repeat {
+          let (rest, field) = Self::parse_field(kind, sample)?;
+          fields.push(field);
+          sample = rest;
+        }
+      }
+
+      samples.push(fields);
+    }
+
+    Ok((input, samples))
+  }
+
+  pub fn parse_sample(kind: u8, input: &[u8]) -> IResult<'_, Vec<Self>> {
+    match kind {
+      kind::STRING | kind::UTF8 => Ok((&[], vec![Self::String(Self::text(kind, input))])),
+
+      // cameras without a GPS lock may write a date that doesn't parse, keep it as a string.
+      kind::DATETIME => Ok((&[], vec![Self::datetime(input)])),
+
+      kind => all_consuming(many0(|input| Self::parse_one(kind, input)))(input),
+    }
+  }
+
+  // Parses a single non-string field of a complex sample.
+  fn parse_field(kind: u8, input: &[u8]) -> IResult<'_, Self> {
+    match kind {
+      kind::DATETIME => map(take(16_usize), Self::datetime)(input),
+      kind => Self::parse_one(kind, input),
+    }
+  }
+
+  fn parse_one(kind: u8, input: &[u8]) -> IResult<'_, Self> {
+    match kind {
+      kind::I8 => map(be_i8, Self::I8)(input),
+      kind::U8 => map(be_u8, Self::U8)(input),
+      kind::I16 => map(be_i16, Self::I16)(input),
+      kind::U16 => map(be_u16, Self::U16)(input),
+      kind::I32 => map(be_i32, Self::I32)(input),
+      kind::U32 => map(be_u32, Self::U32)(input),
+      kind::I64 => map(be_i64, Self::I64)(input),
+      kind::U64 => map(be_u64, Self::U64)(input),
+      kind::F32 => map(be_f32, Self::F32)(input),
+      kind::F64 => map(be_f64, Self::F64)(input),
+      kind::FIXED32 => map(be_i32, |q| Self::Fixed32(f64::from(q) / f64::from(1 << 16)))(input),
+      kind::FIXED64 => map(be_i64, |q| Self::Fixed64(q as f64 / (1_u64 << 32) as f64))(input),
+      kind::FOURCC => map(take(4_usize), |key: &[u8]| Self::FourCC(Self::string(key)))(input),
+      kind::UUID => map(take(16_usize), |id: &[u8]| Self::Uuid(Uuid::from_slice(id).unwrap()))(input),
+      _ => fail(input),
+    }
+  }
+
+  // Splits the TYPE of a complex sample into type characters and their repeat counts, where
+  // `f[4]` is four `f` fields and `c[4]` a single string of four characters. Returns None if any
+  // type is unknown.
+  pub fn struct_kinds(types: &str) -> Option<Vec<(u8, usize)>> {
+    let mut kinds = Vec::new();
+    let mut chars = types.chars().peekable();
+
+    while let Some(c) = chars.next() {
+      if !c.is_ascii() || !Self::is_known(c as u8) {
+        return None;
+      }
+
+      let mut repeat = 1;
+      if chars.peek() == Some(&'[') {
+        chars.next();
+
+        let digits: String = chars.by_ref().take_while(|c| *c != ']').collect();
+        repeat = digits.parse().ok()?;
+      }
+
+      kinds.push((c as u8, repeat));
+    }
+
+    Some(kinds)
+  }
+
+  // Whether `kind` is a type character this module knows how to decode.
+  pub fn is_known(kind: u8) -> bool {
+    matches!(
+      kind,
+      kind::I8
+        | kind::U8
+        | kind::I16
+        | kind::U16
+        | kind::I32
+        | kind::U32
+        | kind::I64
+        | kind::U64
+        | kind::F32
+        | kind::F64
+        | kind::FIXED32
+        | kind::FIXED64
+        | kind::STRING
+        | kind::UTF8
+        | kind::FOURCC
+        | kind::UUID
+        | kind::DATETIME
+    )
+  }
+
+  pub fn as_f64(&self) -> Option<f64> {
+    match *self {
+      Self::I8(x) => Some(f64::from(x)),
+      Self::U8(x) => Some(f64::from(x)),
+      Self::I16(x) => Some(f64::from(x)),
+      Self::U16(x) => Some(f64::from(x)),
+      Self::I32(x) => Some(f64::from(x)),
+      Self::U32(x) => Some(f64::from(x)),
+      Self::I64(x) => Some(x as f64),
+      Self::U64(x) => Some(x as f64),
+      Self::F32(x) => Some(f64::from(x)),
+      Self::F64(x) | Self::Fixed32(x) | Self::Fixed64(x) => Some(x),
+      Self::String(_) | Self::FourCC(_) | Self::Uuid(_) | Self::DateTime(_) => None,
+    }
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Self::String(s) | Self::FourCC(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn scaled(&self, divisor: f64) -> Self {
+    match self.as_f64() {
+      Some(x) => Self::F64(x / divisor),
+      None => self.clone(),
+    }
+  }
+
+  fn datetime(input: &[u8]) -> Self {
+    let string = Self::string(input);
+
+    match NaiveDateTime::parse_from_str(&string, "%y%m%d%H%M%S%.f") {
+      Ok(datetime) => Self::DateTime(datetime),
+      Err(_) => Self::String(string),
+    }
+  }
+
+  // The size in bytes of a single element of `kind`.
+  fn size(kind: u8) -> Option<usize> {
+    match kind {
+      kind::I8 | kind::U8 | kind::STRING | kind::UTF8 => Some(1),
+      kind::I16 | kind::U16 => Some(2),
+      kind::I32 | kind::U32 | kind::F32 | kind::FIXED32 | kind::FOURCC => Some(4),
+      kind::I64 | kind::U64 | kind::F64 | kind::FIXED64 => Some(8),
+      kind::UUID | kind::DATETIME => Some(16),
+      _ => None,
+    }
+  }
+
+  fn is_text(kind: u8) -> bool {
+    kind == kind::STRING || kind == kind::UTF8
+  }
+
+  // `c` strings are Latin-1, `u` strings UTF-8.
+  fn text(kind: u8, input: &[u8]) -> String {
+    match kind {
+      kind::UTF8 => String::from_utf8_lossy(input.split(|b| *b == 0).next().unwrap_or_default()).into_owned(),
+      _ => Self::string(input),
+    }
+  }
+
+  // GPMF strings are fixed width and padded with nulls.
+  fn string(input: &[u8]) -> String {
+    input.iter().take_while(|b| **b != 0).map(|b| *b as char).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::NaiveDate;
+
+  use super::*;
+
+  fn samples(kind: u8, size: usize, count: usize, input: &[u8]) -> Vec<Vec<Value>> {
+    Value::parse_samples(kind, size, count, input).unwrap().1
+  }
+
+  #[test]
+  fn numbers() {
+    let data = [0x00, 0x01, 0xff, 0xfe, 0x7f, 0xff, 0x00, 0x02, 0x00, 0x03, 0x80, 0x00];
+
+    assert_eq!(
+      samples(kind::I16, 6, 2, &data),
+      vec![
+        vec![Value::I16(1), Value::I16(-2), Value::I16(i16::MAX)],
+        vec![Value::I16(2), Value::I16(3), Value::I16(i16::MIN)],
+      ]
+    );
+    assert_eq!(
+      samples(kind::U32, 4, 1, &[0xff, 0xff, 0xff, 0xff]),
+      vec![vec![Value::U32(u32::MAX)]]
+    );
+    assert_eq!(samples(kind::F32, 4, 1, &1.5_f32.to_be_bytes()), vec![vec![Value::F32(1.5)]]);
+    assert_eq!(
+      samples(kind::FIXED32, 4, 1, &[0x00, 0x01, 0x80, 0x00]),
+      vec![vec![Value::Fixed32(1.5)]]
+    );
+    assert_eq!(
+      samples(kind::FIXED64, 8, 1, &[0xff, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00]),
+      vec![vec![Value::Fixed64(-0.5)]]
+    );
+  }
+
+  #[test]
+  fn text() {
+    assert_eq!(
+      samples(kind::STRING, 1, 7, b"HERO10\0"),
+      vec![vec![Value::String("HERO10".to_string())]]
+    );
+    assert_eq!(
+      samples(kind::STRING, 4, 2, b"ab\0\0cd\0\0"),
+      vec![vec![Value::String("ab".to_string())], vec![Value::String("cd".to_string())]]
+    );
+    assert_eq!(samples(kind::FOURCC, 4, 1, b"GPS5"), vec![vec![Value::FourCC("GPS5".to_string())]]);
+  }
+
+  #[test]
+  fn dates() {
+    let date = NaiveDate::from_ymd(2023, 4, 5).and_hms_milli(12, 34, 56, 789);
+
+    assert_eq!(
+      samples(kind::DATETIME, 16, 1, b"230405123456.789"),
+      vec![vec![Value::DateTime(date)]]
+    );
+
+    // cameras without a lock write zeros.
+    assert_eq!(
+      samples(kind::DATETIME, 16, 1, b"000000000000.000"),
+      vec![vec![Value::String("000000000000.000".to_string())]]
+    );
+  }
+
+  #[test]
+  fn unknown_kind() {
+    assert!(Value::parse_samples(b'x', 4, 1, &[0; 4]).is_err());
+  }
+
+  #[test]
+  fn truncated_samples() {
+    assert!(Value::parse_samples(kind::U16, 4, 2, &[0; 6]).is_err());
+  }
+
+  #[test]
+  fn struct_kinds() {
+    assert_eq!(
+      Value::struct_kinds("lllllllSS"),
+      Some(vec![
+        (b'l', 1),
+        (b'l', 1),
+        (b'l', 1),
+        (b'l', 1),
+        (b'l', 1),
+        (b'l', 1),
+        (b'l', 1),
+        (b'S', 1),
+        (b'S', 1)
+      ])
+    );
+    assert_eq!(Value::struct_kinds("f[4]c[8]B"), Some(vec![(b'f', 4), (b'c', 8), (b'B', 1)]));
+    assert_eq!(Value::struct_kinds("fx"), None);
+    assert_eq!(Value::struct_kinds("f[x]"), None);
+  }
+
+  #[test]
+  fn structs() {
+    let types = Value::struct_kinds("lSc[4]").unwrap();
+    let data = [0xff, 0xff, 0xff, 0xff, 0x00, 0x07, b'a', b'b', 0, 0];
+
+    assert_eq!(
+      Value::parse_structs(&types, 10, 1, &data).unwrap().1,
+      vec![vec![Value::I32(-1), Value::U16(7), Value::String("ab".to_string())]]
+    );
+
+    // a TYPE describing more bytes than a sample holds is rejected.
+    assert!(Value::parse_structs(&types, 8, 1, &data).is_err());
+  }
+}
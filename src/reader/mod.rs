@@ -1,22 +1,34 @@
 mod datetime;
 pub mod destinations;
 mod ffmpeg;
-mod gpmf;
+pub mod gpmf;
+pub mod mp4;
+pub mod places;
+mod timezone;
+pub mod track;
 
-use std::{path::Path, sync::mpsc::Sender, thread};
+use std::{iter, path::Path, sync::mpsc::Sender, thread};
 
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
-  cache::Source as SourceCache,
+  cache::{File as CachedFile, Source as SourceCache, PROBED},
   channel::{EventChannel, ResultChannel},
   error::Result,
   events::Event,
   mode::Mode,
-  ui::state::session::File,
+  ui::state::session::{File, Sidecar, SidecarKind, Video, UNKNOWN_DATE},
   utils,
 };
 
+use self::{
+  gpmf::{clock::Clock, Gpmf},
+  mp4::Mp4,
+  track::{Fixes, Point},
+};
+
 pub fn spawn(mode: &Mode, event_channel: &EventChannel, result_channel: &ResultChannel, cache: SourceCache) {
   let input_dir = mode.input_dir();
   let event_sender = event_channel.sender();
@@ -37,36 +49,195 @@ fn run(input_dir: &Path, event_sender: &Sender<Event>, cache: &SourceCache) -> R
     let path = file.path();
     let file_name = utils::file_name(path)?;
 
-    let (date, seconds, note, status) = if let Some(file) = cache.get(&file_name) {
-      if file.imported {
-        continue;
-      }
+    let mp4 = Mp4::open(path).ok();
+    let camera = mp4.as_ref().and_then(|mp4| mp4.camera().ok());
+    let highlights = mp4.as_ref().and_then(|mp4| mp4.highlights().ok()).unwrap_or_default();
+    let sidecars = sidecars(path);
 
-      (file.date, file.seconds, file.note, file.status)
-    } else {
-      let ffprobe_info = ffmpeg::ffprobe(path)?;
-      let date = if let Ok(datetime) = datetime::approximate(path, &ffprobe_info) {
-        datetime.naive_local().date().to_string()
-      } else {
-        "?".to_string()
-      };
+    // files are cached by their media unique ID, older caches are keyed by file name. a file name
+    // only matches entries without a media unique ID, as names repeat between recordings.
+    let cached = match camera.as_ref().and_then(|camera| camera.media_id.as_ref()) {
+      Some(media_id) => cache.get(media_id).or_else(|| {
+        cache
+          .get(&file_name)
+          .filter(|file| file.camera.as_ref().and_then(|camera| camera.media_id.as_ref()).is_none())
+      }),
+      None => cache.get(&file_name),
+    };
+
+    if cached.as_ref().is_some_and(|file| file.imported) {
+      continue;
+    }
 
-      (date, ffprobe_info.seconds, None, None)
+    // what a cached entry lacks is read from the file, and cached again once the file is added.
+    let cached = match cached {
+      Some(file) if file.is_complete() => file,
+      cached => {
+        let track = cached.as_ref().is_none_or(|file| !file.has_track());
+        complete(cached, probe(path, mp4, track)?)
+      }
     };
 
     event_sender.send(Event::File(Box::new(File {
       path: path.to_path_buf(),
       metadata: file.metadata()?,
-      date,
-      seconds,
-      note,
-      status,
+      date: cached.date,
+      timezone: cached.timezone,
+      seconds: cached.seconds,
+      video: cached.video,
+      camera,
+      stats: cached.stats,
+      profile: cached.profile,
+      route: cached.route,
+      highlights,
+      sidecars,
+      note: cached.note,
+      status: cached.status,
+      chapters: Vec::new(),
     })))?;
   }
 
   Ok(())
 }
 
+// Fills in what `cached` lacks from a probe of its file, or makes a new entry from it.
+fn complete(cached: Option<CachedFile>, probe: Probe) -> CachedFile {
+  let (date, timezone) = match probe.datetime {
+    Ok((datetime, timezone)) => (datetime.date().to_string(), timezone.map(|timezone| timezone.name().to_string())),
+    Err(_) => (UNKNOWN_DATE.to_string(), None),
+  };
+
+  let stats = track::stats(&probe.points);
+  let profile = track::profile(&probe.points, probe.seconds);
+  let route = track::route(&probe.points);
+
+  match cached {
+    Some(cached) => CachedFile {
+      date: if cached.timezone.is_some() { cached.date } else { date },
+      timezone: cached.timezone.or(timezone),
+      video: cached.video.or(probe.video),
+      stats: cached.stats.or(stats),
+      profile: cached.profile.or(profile),
+      route: if cached.route.is_empty() { route } else { cached.route },
+      probed: PROBED,
+      ..cached
+    },
+
+    None => CachedFile {
+      date,
+      timezone,
+      seconds: probe.seconds,
+      video: probe.video,
+      camera: None,
+      stats,
+      profile,
+      route,
+      note: None,
+      status: None,
+      probed: PROBED,
+      imported: false,
+    },
+  }
+}
+
+struct Probe {
+  seconds: f64,
+  video: Option<Video>,
+  datetime: Result<(NaiveDateTime, Option<Tz>)>,
+  points: Vec<Point>,
+}
+
+// Reads stream information and the gpmd track natively, only falling back to ffprobe and
+// ffmpeg for files whose boxes can't be parsed. GPS fixes are collected from the payloads read
+// for the date, and the rest of the payloads are only read when `track` is set.
+fn probe(path: &Path, mp4: Option<Mp4>, track: bool) -> Result<Probe> {
+  let mut fixes = Fixes::default();
+
+  match mp4.map(|mp4| mp4.seconds().map(|seconds| (seconds, mp4))) {
+    Some(Ok((seconds, mut mp4))) => {
+      let video = mp4.video().ok();
+      // files without a gpmd track still get a date from their modified time.
+      let datetime = match mp4.gpmf_payloads() {
+        Ok(payloads) => {
+          let mut payloads = payloads.recovering().inspect(|payload| {
+            if let Ok(payload) = payload {
+              fixes.add(payload);
+            }
+          });
+
+          let datetime = datetime::approximate(path, payloads.by_ref());
+
+          // like `track::read`, the track ends at the first payload that can't be read.
+          if track {
+            for payload in payloads {
+              if payload.is_err() {
+                break;
+              }
+            }
+          }
+
+          datetime
+        }
+
+        Err(_) => datetime::approximate(path, iter::empty()),
+      };
+
+      Ok(Probe {
+        seconds,
+        video,
+        datetime,
+        points: fixes.points(),
+      })
+    }
+
+    _ => {
+      let ffprobe_info = ffmpeg::ffprobe(path)?;
+
+      let gpmf = ffmpeg::gpmf_data(path, &ffprobe_info).map(|data| {
+        let (mut gpmf, _) = Gpmf::parse_recovering(&data);
+        Clock::default().time_evenly(&mut gpmf, ffprobe_info.seconds);
+
+        gpmf
+      });
+
+      // the whole payload is in memory already, so the track costs nothing extra.
+      if let Ok(gpmf) = &gpmf {
+        fixes.add(gpmf);
+      }
+
+      Ok(Probe {
+        seconds: ffprobe_info.seconds,
+        video: None,
+        datetime: datetime::approximate(path, gpmf.into_iter().flatten().map(|payload| Ok(vec![payload]))),
+        points: fixes.points(),
+      })
+    }
+  }
+}
+
 fn is_mp4(entry: &DirEntry) -> bool {
   matches!(entry.path().extension(), Some(ext) if ext == "MP4")
 }
+
+// Sidecar files are next to the video they belong to and share its name, except for the low
+// resolution proxies of newer cameras, which are named GL010123.LRV for GX010123.MP4.
+fn sidecars(path: &Path) -> Vec<Sidecar> {
+  let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+  let proxy_stem = match stem.get(2..) {
+    Some(number) if stem.starts_with('G') && number.bytes().all(|b| b.is_ascii_digit()) => Some(format!("GL{}", number)),
+    _ => None,
+  };
+
+  SidecarKind::ALL
+    .into_iter()
+    .flat_map(|kind| {
+      let proxy_stem = proxy_stem.as_ref().filter(|_| kind == SidecarKind::Lrv);
+
+      iter::once(&stem)
+        .chain(proxy_stem)
+        .map(move |stem| path.with_file_name(format!("{}.{}", stem, kind.extension())))
+        .filter(|path| path.is_file())
+        .map(move |path| Sidecar { kind, path })
+    })
+    .collect()
+}
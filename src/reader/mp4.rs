@@ -0,0 +1,729 @@
+use std::{
+  cmp,
+  fs::File,
+  io::{Read, Seek, SeekFrom},
+  iter,
+  path::Path,
+  vec,
+};
+
+use nom::{
+  bytes::complete::take,
+  combinator::{all_consuming, fail, map},
+  multi::{count, many0},
+  number::complete::{be_u32, be_u64},
+  sequence::pair,
+  IResult,
+};
+
+use super::gpmf::{clock::Clock, error::ParseError, Gpmf};
+use crate::{
+  error::{err, Result, WrapErr},
+  ui::state::session::{Camera, Video},
+};
+
+// A reader for the boxes of an MP4/MOV file.
+//
+// Only the `moov` box is kept in memory, samples are read from the file by offset.
+pub struct Mp4 {
+  file: File,
+  file_size: u64,
+  moov: Vec<u8>,
+}
+
+// The location of a single sample in the file, and the window of the track it spans in seconds.
+pub struct Sample {
+  pub offset: u64,
+  pub size: u64,
+  pub time: f64,
+  pub duration: f64,
+}
+
+pub struct Payloads<'a> {
+  mp4: &'a mut Mp4,
+  samples: vec::IntoIter<Sample>,
+  clock: Clock,
+  recovering: bool,
+
+  // the errors of skipped DEVC blocks, with the file offset of the sample they were in.
+  errors: Vec<(u64, ParseError)>,
+}
+
+impl Payloads<'_> {
+  // Skips damaged DEVC blocks instead of failing the whole payload.
+  pub fn recovering(mut self) -> Self {
+    self.recovering = true;
+    self
+  }
+
+  pub fn errors(&self) -> &[(u64, ParseError)] {
+    &self.errors
+  }
+}
+
+impl Iterator for Payloads<'_> {
+  type Item = Result<Vec<Gpmf>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let sample = self.samples.next()?;
+
+    let payload = self.mp4.read_sample(&sample).and_then(|data| {
+      let mut payload = if self.recovering {
+        let (payload, errors) = Gpmf::parse_recovering(&data);
+        self.errors.extend(errors.into_iter().map(|error| (sample.offset, error)));
+
+        payload
+      } else {
+        Gpmf::parse(&data).wrap_err_with(|| format!("In the gpmd sample at byte {}", sample.offset))?
+      };
+
+      self.clock.time(&mut payload, sample.time, sample.duration);
+
+      Ok(payload)
+    });
+
+    Some(payload)
+  }
+}
+
+struct Mp4Box<'a> {
+  kind: String,
+  data: &'a [u8],
+}
+
+impl Mp4 {
+  pub fn open(path: &Path) -> Result<Self> {
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let moov = Self::read_moov(&mut file, file_size)?;
+
+    Ok(Self { file, file_size, moov })
+  }
+
+  // The duration of the movie in seconds, from `mvhd`.
+  pub fn seconds(&self) -> Result<f64> {
+    let mvhd = child(&self.moov, &["mvhd"]).ok_or(err!("no mvhd box found"))?;
+    let (timescale, duration) = parse(mvhd_timing(mvhd), "mvhd")?;
+
+    Ok(duration as f64 / f64::from(timescale))
+  }
+
+  // Stream information of the first video track.
+  pub fn video(&self) -> Result<Video> {
+    let trak = self
+      .traks()?
+      .into_iter()
+      .find(|trak| child(trak.data, &["mdia", "hdlr"]).and_then(handler_type).as_deref() == Some("vide"))
+      .ok_or(err!("no video track found"))?;
+
+    let tkhd = child(trak.data, &["tkhd"]).ok_or(err!("no tkhd box found"))?;
+    let mdhd = child(trak.data, &["mdia", "mdhd"]).ok_or(err!("no mdhd box found"))?;
+    let stbl = child(trak.data, &["mdia", "minf", "stbl"]).ok_or(err!("no stbl box found"))?;
+    let stts = child(stbl, &["stts"]).ok_or(err!("no stts box found"))?;
+    let stsz_data = child(stbl, &["stsz"]).ok_or(err!("no stsz box found"))?;
+
+    let (width, height) = parse(tkhd_dimensions(tkhd), "tkhd")?;
+    let (timescale, duration) = parse(mdhd_timing(mdhd), "mdhd")?;
+    let frames: u64 = parse(stts_entries(stts), "stts")?.iter().map(|(count, _)| u64::from(*count)).sum();
+    let bytes = parse(stsz(stsz_data), "stsz")?.total();
+
+    let seconds = duration as f64 / f64::from(timescale);
+
+    Ok(Video {
+      width,
+      height,
+      frame_rate: if seconds > 0.0 { frames as f64 / seconds } else { 0.0 },
+      codec: child(stbl, &["stsd"]).and_then(sample_format).unwrap_or_default(),
+      bitrate: if seconds > 0.0 { (bytes as f64 * 8.0 / seconds) as u64 } else { 0 },
+    })
+  }
+
+  // The samples of the track whose sample description has the format `gpmd`.
+  pub fn gpmd_samples(&self) -> Result<Vec<Sample>> {
+    let mdia = self
+      .traks()?
+      .into_iter()
+      .filter_map(|trak| child(trak.data, &["mdia"]))
+      .find(|mdia| child(mdia, &["minf", "stbl", "stsd"]).and_then(sample_format).as_deref() == Some("gpmd"))
+      .ok_or(err!("no gpmd track found"))?;
+
+    let mdhd = child(mdia, &["mdhd"]).ok_or(err!("no mdhd box found"))?;
+    let (timescale, _) = parse(mdhd_timing(mdhd), "mdhd")?;
+
+    samples(
+      child(mdia, &["minf", "stbl"]).ok_or(err!("no stbl box found"))?,
+      timescale,
+      self.file_size,
+    )
+  }
+
+  // Lazily reads and parses the GPMF payload of each sample of the `gpmd` track, with
+  // clip-relative sample times.
+  pub fn gpmf_payloads(&mut self) -> Result<Payloads<'_>> {
+    Ok(Payloads {
+      samples: self.gpmd_samples()?.into_iter(),
+      clock: Clock::default(),
+      recovering: false,
+      errors: Vec::new(),
+      mp4: self,
+    })
+  }
+
+  pub fn read_sample(&mut self, sample: &Sample) -> Result<Vec<u8>> {
+    // sizes come from the file, check them before allocating.
+    if sample.size > self.file_size.saturating_sub(sample.offset) {
+      return Err(err!(
+        "sample of {} bytes at offset {} is past the end of the file",
+        sample.size,
+        sample.offset
+      ));
+    }
+
+    let mut data = vec![0; sample.size as usize];
+
+    self.file.seek(SeekFrom::Start(sample.offset))?;
+    self.file.read_exact(&mut data)?;
+
+    Ok(data)
+  }
+
+  // The identity of the camera that recorded the file, from the boxes of `udta` and the GPMF
+  // payload stored in its `GPMF` box. Values from the GPMF payload are preferred.
+  pub fn camera(&self) -> Result<Camera> {
+    let udta = child(&self.moov, &["udta"]).ok_or(err!("no udta box found"))?;
+    let udta_boxes = parse(parse_boxes(udta), "udta")?;
+    let mut camera = Camera::default();
+
+    for udta_box in &udta_boxes {
+      match udta_box.kind.as_str() {
+        "FIRM" => camera.firmware = text(udta_box.data),
+        "CAME" => camera.model = text(udta_box.data),
+        "LENS" => camera.lens = text(udta_box.data),
+        "MUID" => camera.media_id = Some(hex(udta_box.data)),
+        _ => (),
+      }
+    }
+
+    if let Some(gpmf) = udta_boxes.iter().find(|udta_box| udta_box.kind == "GPMF") {
+      udta_gpmf(&mut camera, &Gpmf::parse_recovering(gpmf.data).0);
+    }
+
+    Ok(camera)
+  }
+
+  // The HiLight tags of the file in seconds, sorted. Older cameras store them in the `HMMT` box of
+  // `udta`, newer ones as `HLMT` entries of the GPMF payload stored in its `GPMF` box.
+  pub fn highlights(&self) -> Result<Vec<f64>> {
+    let udta = child(&self.moov, &["udta"]).ok_or(err!("no udta box found"))?;
+    let udta_boxes = parse(parse_boxes(udta), "udta")?;
+    let mut milliseconds = Vec::new();
+
+    for udta_box in &udta_boxes {
+      match udta_box.kind.as_str() {
+        "HMMT" => milliseconds.extend(parse(hmmt(udta_box.data), "HMMT")?),
+        "GPMF" => udta_highlights(&mut milliseconds, &Gpmf::parse_recovering(udta_box.data).0, false),
+        _ => (),
+      }
+    }
+
+    let mut seconds: Vec<_> = milliseconds.into_iter().map(|ms| ms / 1000.0).collect();
+    seconds.sort_by(f64::total_cmp);
+    seconds.dedup();
+
+    Ok(seconds)
+  }
+
+  fn traks(&self) -> Result<Vec<Mp4Box<'_>>> {
+    Ok(
+      parse(parse_boxes(&self.moov), "moov")?
+        .into_iter()
+        .filter(|b| b.kind == "trak")
+        .collect(),
+    )
+  }
+
+  // Seeks through the top-level boxes of the file until `moov` is found.
+  fn read_moov(file: &mut File, file_size: u64) -> Result<Vec<u8>> {
+    let mut position = 0;
+
+    while position < file_size {
+      let mut header = [0; 8];
+      file.seek(SeekFrom::Start(position))?;
+      file.read_exact(&mut header)?;
+
+      let (size, kind) = header.split_at(4);
+      let (header_size, box_size) = match u32::from_be_bytes(size.try_into()?) {
+        0 => (8, file_size - position),
+        1 => {
+          let mut large_size = [0; 8];
+          file.read_exact(&mut large_size)?;
+          (16, u64::from_be_bytes(large_size))
+        }
+        size => (8, u64::from(size)),
+      };
+
+      if box_size < header_size || box_size > file_size - position {
+        return Err(err!("invalid mp4 box size {} at offset {}", box_size, position));
+      }
+
+      if kind == b"moov" {
+        let mut moov = vec![0; (box_size - header_size) as usize];
+        file.read_exact(&mut moov)?;
+
+        return Ok(moov);
+      }
+
+      position += box_size;
+    }
+
+    Err(err!("no moov box found"))
+  }
+}
+
+fn parse<T>(result: IResult<&[u8], T>, kind: &str) -> Result<T> {
+  result
+    .map(|(_, parsed)| parsed)
+    .map_err(|error| err!("Failed to parse {} box: {}", kind, error))
+}
+
+fn parse_box(input: &[u8]) -> IResult<&[u8], Mp4Box<'_>> {
+  let (input, size) = be_u32(input)?;
+  let (input, kind) = take(4_usize)(input)?;
+
+  let (input, data_size) = match size {
+    0 => (input, input.len() as u64),
+    1 => {
+      let (input, large_size) = be_u64(input)?;
+      (input, large_size.saturating_sub(16))
+    }
+    size => (input, u64::from(size).saturating_sub(8)),
+  };
+
+  let (input, data) = take(data_size)(input)?;
+
+  Ok((
+    input,
+    Mp4Box {
+      kind: kind.iter().map(|b| *b as char).collect(),
+      data,
+    },
+  ))
+}
+
+fn parse_boxes(input: &[u8]) -> IResult<&[u8], Vec<Mp4Box<'_>>> {
+  all_consuming(many0(parse_box))(input)
+}
+
+// Finds a descendant box by following a path of box kinds.
+fn child<'a>(data: &'a [u8], path: &[&str]) -> Option<&'a [u8]> {
+  match path {
+    [] => Some(data),
+    [kind, rest @ ..] => {
+      let (_, boxes) = parse_boxes(data).ok()?;
+      let found = boxes.into_iter().find(|b| b.kind == *kind)?;
+
+      child(found.data, rest)
+    }
+  }
+}
+
+// The format of the first entry of an `stsd` box.
+fn sample_format(stsd: &[u8]) -> Option<String> {
+  // version and flags, entry count, entry size.
+  let format = stsd.get(12..16)?;
+
+  Some(format.iter().map(|b| *b as char).collect())
+}
+
+// The handler type of an `hdlr` box, e.g. `vide` or `meta`.
+fn handler_type(hdlr: &[u8]) -> Option<String> {
+  // version and flags, pre-defined.
+  let handler_type = hdlr.get(8..12)?;
+
+  Some(handler_type.iter().map(|b| *b as char).collect())
+}
+
+// Full boxes start with a version byte and three bytes of flags.
+fn full_box(input: &[u8]) -> IResult<&[u8], u32> {
+  be_u32(input)
+}
+
+// The timescale and duration of an `mvhd` box, whose version decides the width of its times.
+fn mvhd_timing(input: &[u8]) -> IResult<&[u8], (u32, u64)> {
+  let (input, version) = map(full_box, |version_and_flags| version_and_flags >> 24)(input)?;
+
+  if version == 1 {
+    let (input, _) = take(16_usize)(input)?;
+    let (input, timescale) = be_u32(input)?;
+    let (input, duration) = be_u64(input)?;
+
+    Ok((input, (timescale, duration)))
+  } else {
+    let (input, _) = take(8_usize)(input)?;
+    let (input, timescale) = be_u32(input)?;
+    let (input, duration) = be_u32(input)?;
+
+    Ok((input, (timescale, u64::from(duration))))
+  }
+}
+
+// `mdhd` starts with the same timing fields as `mvhd`.
+fn mdhd_timing(input: &[u8]) -> IResult<&[u8], (u32, u64)> {
+  mvhd_timing(input)
+}
+
+// The width and height of a `tkhd` box, stored as 16.16 fixed point numbers.
+fn tkhd_dimensions(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
+  let (input, version) = map(full_box, |version_and_flags| version_and_flags >> 24)(input)?;
+
+  // times, track id and duration, followed by layer, group, volume and matrix.
+  let times_size = if version == 1 { 32 } else { 20 };
+  let (input, _) = take(times_size + 52_usize)(input)?;
+
+  let (input, width) = be_u32(input)?;
+  let (input, height) = be_u32(input)?;
+
+  Ok((input, (width >> 16, height >> 16)))
+}
+
+// The entry count of a sample table box. Counts come from the file, so a count whose entries of
+// `entry_size` bytes don't fit in the rest of the box is rejected before allocating for it.
+fn entry_count(entry_size: usize) -> impl Fn(&[u8]) -> IResult<&[u8], usize> {
+  move |input| {
+    let (rest, entry_count) = be_u32(input)?;
+    let entry_count = entry_count as usize;
+
+    match entry_count.checked_mul(entry_size) {
+      Some(size) if size <= rest.len() => Ok((rest, entry_count)),
+      _ => fail(input),
+    }
+  }
+}
+
+// Each entry is a run of samples with the same duration, as `(sample count, sample delta)`.
+fn stts_entries(input: &[u8]) -> IResult<&[u8], Vec<(u32, u32)>> {
+  let (input, _) = full_box(input)?;
+  let (input, entry_count) = entry_count(8)(input)?;
+
+  count(pair(be_u32, be_u32), entry_count)(input)
+}
+
+// The sizes of the samples of a track, either shared by all of them or one per sample.
+enum SampleSizes {
+  Constant { size: u64, count: u32 },
+  Table(Vec<u64>),
+}
+
+impl SampleSizes {
+  fn total(&self) -> u64 {
+    match self {
+      SampleSizes::Constant { size, count } => size.saturating_mul(u64::from(*count)),
+      SampleSizes::Table(sizes) => sizes.iter().sum(),
+    }
+  }
+
+  fn into_iter(self) -> Box<dyn Iterator<Item = u64>> {
+    match self {
+      SampleSizes::Constant { size, count } => Box::new(iter::repeat_n(size, count as usize)),
+      SampleSizes::Table(sizes) => Box::new(sizes.into_iter()),
+    }
+  }
+}
+
+fn stsz(input: &[u8]) -> IResult<&[u8], SampleSizes> {
+  let (input, _) = full_box(input)?;
+  let (input, sample_size) = be_u32(input)?;
+
+  if sample_size != 0 {
+    let (input, sample_count) = be_u32(input)?;
+
+    return Ok((
+      input,
+      SampleSizes::Constant {
+        size: u64::from(sample_size),
+        count: sample_count,
+      },
+    ));
+  }
+
+  let (input, sample_count) = entry_count(4)(input)?;
+  let (input, sizes) = count(be_u32, sample_count)(input)?;
+
+  Ok((input, SampleSizes::Table(sizes.into_iter().map(u64::from).collect())))
+}
+
+fn stco(input: &[u8]) -> IResult<&[u8], Vec<u64>> {
+  let (input, _) = full_box(input)?;
+  let (input, entry_count) = entry_count(4)(input)?;
+  let (input, offsets) = count(be_u32, entry_count)(input)?;
+
+  Ok((input, offsets.into_iter().map(u64::from).collect()))
+}
+
+fn co64(input: &[u8]) -> IResult<&[u8], Vec<u64>> {
+  let (input, _) = full_box(input)?;
+  let (input, entry_count) = entry_count(8)(input)?;
+
+  count(be_u64, entry_count)(input)
+}
+
+// Each entry is the first chunk (1-indexed) of a run of chunks with the same samples per chunk.
+fn stsc(input: &[u8]) -> IResult<&[u8], Vec<(u32, u32)>> {
+  let (input, _) = full_box(input)?;
+  let (input, entry_count) = entry_count(12)(input)?;
+  let (input, entries) = count(count(be_u32, 3), entry_count)(input)?;
+
+  Ok((input, entries.into_iter().map(|entry| (entry[0], entry[1])).collect()))
+}
+
+// Resolves the offset, size and timing of every sample in a sample table of a file of
+// `file_size` bytes.
+fn samples(stbl: &[u8], timescale: u32, file_size: u64) -> Result<Vec<Sample>> {
+  let sizes = parse(stsz(child(stbl, &["stsz"]).ok_or(err!("no stsz box found"))?), "stsz")?;
+
+  // a constant size doesn't come with a table bounding its count.
+  if let SampleSizes::Constant { size, count } = sizes {
+    if size.checked_mul(u64::from(count)).is_none_or(|total| total > file_size) {
+      return Err(err!("{} samples of {} bytes don't fit in the file", count, size));
+    }
+  }
+
+  let chunk_offsets = match (child(stbl, &["stco"]), child(stbl, &["co64"])) {
+    (Some(stco_data), _) => parse(stco(stco_data), "stco")?,
+    (_, Some(co64_data)) => parse(co64(co64_data), "co64")?,
+    _ => return Err(err!("no stco or co64 box found")),
+  };
+
+  let sample_to_chunk = match child(stbl, &["stsc"]) {
+    Some(stsc_data) => parse(stsc(stsc_data), "stsc")?,
+    None => vec![(1, 1)],
+  };
+
+  let mut deltas = match child(stbl, &["stts"]) {
+    Some(stts) => parse(stts_entries(stts), "stts")?,
+    None => Vec::new(),
+  }
+  .into_iter()
+  .flat_map(|(count, delta)| iter::repeat_n(delta, count as usize));
+
+  let mut sizes = sizes.into_iter();
+  let mut samples = Vec::new();
+  let mut time = 0;
+
+  for (chunk_idx, chunk_offset) in chunk_offsets.into_iter().enumerate() {
+    let chunk = chunk_idx as u32 + 1;
+    let samples_per_chunk = sample_to_chunk
+      .iter()
+      .take_while(|(first_chunk, _)| *first_chunk <= chunk)
+      .last()
+      .map_or(1, |(_, samples_per_chunk)| *samples_per_chunk);
+
+    let mut offset = chunk_offset;
+    for size in sizes.by_ref().take(samples_per_chunk as usize) {
+      let delta = deltas.next().unwrap_or(0);
+
+      samples.push(Sample {
+        offset,
+        size,
+        time: time as f64 / f64::from(timescale),
+        duration: f64::from(delta) / f64::from(timescale),
+      });
+
+      offset += size;
+      time += u64::from(delta);
+    }
+  }
+
+  Ok(samples)
+}
+
+fn udta_gpmf(camera: &mut Camera, gpmf: &[Gpmf]) {
+  for entry in gpmf {
+    match entry {
+      Gpmf::Klv { entries, .. } => udta_gpmf(camera, entries),
+
+      Gpmf::Values { key, samples, .. } => {
+        let values: Vec<_> = samples.iter().flatten().collect();
+        let string = values.first().and_then(|value| value.as_str()).map(str::to_string);
+
+        match key.as_str() {
+          "FIRM" | "FMWR" => camera.firmware = string.or(camera.firmware.take()),
+          "CAME" | "MINF" => camera.model = string.or(camera.model.take()),
+          "LENS" => camera.lens = string.or(camera.lens.take()),
+          "CASN" => camera.serial = string.or(camera.serial.take()),
+          "MUID" => {
+            let words: Option<Vec<_>> = values.iter().map(|value| value.as_f64()).collect();
+            if let Some(words) = words {
+              camera.media_id = Some(words.iter().map(|word| format!("{:08x}", *word as u32)).collect());
+            }
+          }
+          _ => (),
+        }
+      }
+
+      _ => (),
+    }
+  }
+}
+
+// `MANL` values are the HiLight tags pressed on the camera, in milliseconds, when inside an `HLMT`.
+fn udta_highlights(milliseconds: &mut Vec<f64>, gpmf: &[Gpmf], in_hlmt: bool) {
+  for entry in gpmf {
+    match entry {
+      Gpmf::Klv { key, entries, .. } => udta_highlights(milliseconds, entries, in_hlmt || key == "HLMT"),
+
+      Gpmf::Values { key, samples, .. } if in_hlmt && key == "MANL" => {
+        milliseconds.extend(samples.iter().flatten().filter_map(|value| value.as_f64()).filter(|ms| *ms > 0.0));
+      }
+
+      _ => (),
+    }
+  }
+}
+
+// A count followed by that many times in milliseconds. The box has a fixed size on some cameras,
+// with unused slots zeroed.
+fn hmmt(input: &[u8]) -> IResult<&[u8], Vec<f64>> {
+  let (input, entry_count) = be_u32(input)?;
+  let entry_count = cmp::min(entry_count as usize, input.len() / 4);
+  let (input, times) = count(be_u32, entry_count)(input)?;
+
+  Ok((input, times.into_iter().filter(|ms| *ms > 0).map(f64::from).collect()))
+}
+
+// A null-padded string. Older cameras store binary identifiers in some of these boxes.
+fn text(data: &[u8]) -> Option<String> {
+  let data = match data.iter().position(|b| *b == 0) {
+    Some(end) => &data[..end],
+    None => data,
+  };
+
+  let is_printable = !data.is_empty() && data.iter().all(|b| b.is_ascii_graphic() || *b == b' ');
+  is_printable.then(|| String::from_utf8_lossy(data).trim().to_string())
+}
+
+fn hex(data: &[u8]) -> String {
+  data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mp4_box(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    [&(data.len() as u32 + 8).to_be_bytes(), kind.as_slice(), data].concat()
+  }
+
+  // a version 0 full box with big-endian u32 fields.
+  fn full_box(kind: &[u8; 4], fields: &[u32]) -> Vec<u8> {
+    let data: Vec<u8> = iter::once(0).chain(fields.iter().copied()).flat_map(u32::to_be_bytes).collect();
+
+    mp4_box(kind, &data)
+  }
+
+  #[test]
+  fn nested_boxes() {
+    let moov = [
+      mp4_box(b"mvhd", &[1]),
+      mp4_box(
+        b"trak",
+        &mp4_box(b"mdia", &[mp4_box(b"hdlr", &[2]), mp4_box(b"mdhd", &[3])].concat()),
+      ),
+    ]
+    .concat();
+
+    assert_eq!(child(&moov, &["trak", "mdia", "mdhd"]), Some([3].as_slice()));
+    assert_eq!(child(&moov, &["trak", "mdhd"]), None);
+  }
+
+  #[test]
+  fn box_sizes() {
+    // a size of 0 extends to the end of the input, a size of 1 is followed by a 64-bit size.
+    let to_end = [&[0, 0, 0, 0], b"mdat".as_slice(), &[1, 2, 3]].concat();
+    let large = [&[0, 0, 0, 1], b"mdat".as_slice(), &19_u64.to_be_bytes(), &[1, 2, 3]].concat();
+
+    for input in [to_end, large] {
+      let (rest, mdat) = parse_box(&input).unwrap();
+      assert_eq!((mdat.kind.as_str(), mdat.data, rest), ("mdat", [1, 2, 3].as_slice(), [].as_slice()));
+    }
+  }
+
+  #[test]
+  fn truncated_boxes() {
+    let mut moov = [mp4_box(b"mvhd", &[1]), mp4_box(b"trak", &[2; 16])].concat();
+    moov.truncate(moov.len() - 1);
+
+    assert!(parse_boxes(&moov).is_err());
+    assert!(parse_boxes(&moov[..4]).is_err());
+    assert_eq!(child(&moov, &["mvhd"]), None);
+  }
+
+  #[test]
+  fn oversized_entry_counts() {
+    // counts whose entries don't fit in the rest of the box.
+    let stts_box = full_box(b"stts", &[u32::MAX, 1, 1000]);
+    let stco_box = full_box(b"stco", &[2, 100]);
+    let stsc_box = full_box(b"stsc", &[0x1555_5556, 1, 1, 1]);
+    let stsz_box = full_box(b"stsz", &[0, 3, 10, 20]);
+
+    assert!(stts_entries(&stts_box[8..]).is_err());
+    assert!(stco(&stco_box[8..]).is_err());
+    assert!(stsc(&stsc_box[8..]).is_err());
+    assert!(stsz(&stsz_box[8..]).is_err());
+  }
+
+  #[test]
+  fn sample_sizes() {
+    let constant = full_box(b"stsz", &[512, 3]);
+    let table = full_box(b"stsz", &[0, 3, 10, 20, 30]);
+
+    let (_, constant) = stsz(&constant[8..]).unwrap();
+    assert_eq!(constant.total(), 1536);
+    assert_eq!(constant.into_iter().collect::<Vec<_>>(), vec![512, 512, 512]);
+
+    let (_, table) = stsz(&table[8..]).unwrap();
+    assert_eq!(table.total(), 60);
+    assert_eq!(table.into_iter().collect::<Vec<_>>(), vec![10, 20, 30]);
+  }
+
+  #[test]
+  fn sample_table() {
+    // two chunks, the first with two samples and the second with one.
+    let stbl = [
+      full_box(b"stsz", &[0, 3, 10, 20, 30]),
+      full_box(b"stco", &[2, 100, 200]),
+      full_box(b"stsc", &[2, 1, 2, 1, 2, 1, 1]),
+      full_box(b"stts", &[1, 3, 500]),
+    ]
+    .concat();
+
+    let samples = samples(&stbl, 1000, 1000).unwrap();
+    let samples: Vec<_> = samples.iter().map(|s| (s.offset, s.size, s.time, s.duration)).collect();
+
+    assert_eq!(samples, vec![(100, 10, 0.0, 0.5), (110, 20, 0.5, 0.5), (200, 30, 1.0, 0.5)]);
+  }
+
+  #[test]
+  fn constant_samples_past_the_file() {
+    let stbl = [full_box(b"stsz", &[512, u32::MAX]), full_box(b"stco", &[1, 0])].concat();
+
+    assert!(samples(&stbl, 1000, 1 << 20).is_err());
+  }
+
+  #[test]
+  fn hmmt_counts() {
+    // a fixed size box with unused slots zeroed, and a count larger than the box.
+    let fixed = [2_u32, 1500, 3000, 0, 0].iter().flat_map(|n| n.to_be_bytes()).collect::<Vec<_>>();
+    let oversized = [100_u32, 1500].iter().flat_map(|n| n.to_be_bytes()).collect::<Vec<_>>();
+
+    assert_eq!(hmmt(&fixed).unwrap().1, vec![1500.0, 3000.0]);
+    assert_eq!(hmmt(&oversized).unwrap().1, vec![1500.0]);
+  }
+
+  #[test]
+  fn texts() {
+    assert_eq!(text(b"HD9.01.01.60.00\0\0\0").as_deref(), Some("HD9.01.01.60.00"));
+    assert_eq!(text(&[0x12, 0x34, 0, 0]), None);
+    assert_eq!(text(&[0, 0]), None);
+  }
+}
@@ -0,0 +1,104 @@
+use std::{collections::BTreeSet, fmt};
+
+use crate::utils;
+
+const PLACES: &str = include_str!("../../data/places.txt");
+
+// Coordinates further than this many meters from every place are left unnamed.
+const MAX_DISTANCE: f64 = 250_000.0;
+
+#[derive(Clone)]
+pub struct Place {
+  pub name: String,
+  pub country: String,
+}
+
+impl fmt::Display for Place {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}, {}", self.name, self.country)
+  }
+}
+
+// Finds the place nearest to a coordinate, from the bundled gazetteer.
+pub fn nearest(latitude: f64, longitude: f64) -> Option<Place> {
+  let (distance, place) = PLACES
+    .lines()
+    .filter_map(place)
+    .map(|(point, place)| (utils::distance((latitude, longitude), point), place))
+    .min_by(|(a, _), (b, _)| a.total_cmp(b))?;
+
+  let (country, name) = place.split_once(' ')?;
+
+  (distance < MAX_DISTANCE).then(|| Place {
+    name: name.trim().to_string(),
+    country: country.to_string(),
+  })
+}
+
+// A regex matching the names of places, made of the characters the bundled names use so that it
+// can't match across path separators.
+pub fn name_pattern() -> String {
+  let chars: BTreeSet<char> = PLACES
+    .lines()
+    .filter_map(place)
+    .filter_map(|(_, place)| place.split_once(' '))
+    .flat_map(|(_, name)| name.trim().chars())
+    .collect();
+
+  format!("[{}]*?", regex::escape(&chars.into_iter().collect::<String>()))
+}
+
+// The coordinate of a place and its `country name`, names can contain spaces.
+fn place(line: &str) -> Option<((f64, f64), &str)> {
+  if line.starts_with('#') {
+    return None;
+  }
+
+  let mut fields = line.splitn(3, ' ');
+
+  let latitude = fields.next()?.parse().ok()?;
+  let longitude = fields.next()?.parse().ok()?;
+  let place = fields.next()?;
+
+  Some(((latitude, longitude), place))
+}
+
+#[cfg(test)]
+mod tests {
+  use regex::Regex;
+
+  use super::*;
+
+  fn name(latitude: f64, longitude: f64) -> Option<String> {
+    nearest(latitude, longitude).map(|place| place.to_string())
+  }
+
+  #[test]
+  fn border_towns() {
+    // towns without their own entry are named after the nearest place, across borders too.
+    assert_eq!(name(48.5725, 7.8150).as_deref(), Some("Strasbourg, FR"));
+    assert_eq!(name(47.5900, 7.5600).as_deref(), Some("Basel, CH"));
+    assert_eq!(name(46.1930, 6.2340).as_deref(), Some("Geneva, CH"));
+    assert_eq!(name(42.3149, -83.0364).as_deref(), Some("Detroit, US"));
+    assert_eq!(name(31.7400, -106.4870).as_deref(), Some("El Paso, US"));
+
+    // either side of the San Ysidro crossing.
+    assert_eq!(name(32.5430, -117.0290).as_deref(), Some("Tijuana, MX"));
+    assert_eq!(name(32.7000, -117.1500).as_deref(), Some("San Diego, US"));
+  }
+
+  #[test]
+  fn far_from_every_place() {
+    assert_eq!(name(-40.0, -130.0), None);
+    assert_eq!(name(0.0, 0.0), None);
+  }
+
+  #[test]
+  fn names() {
+    let pattern = Regex::new(&format!("^{}$", name_pattern())).unwrap();
+
+    assert!(pattern.is_match("San Diego"));
+    assert!(pattern.is_match("Geneva"));
+    assert!(!pattern.is_match("Geneva/DCIM"));
+  }
+}
@@ -0,0 +1,156 @@
+use chrono_tz::Tz;
+
+const OUTLINES: &str = include_str!("../../data/timezones.txt");
+
+// Simplified coastlines cut off some shores, so points this many degrees outside every outline still belong to one.
+const NEARBY: f64 = 0.5;
+
+// Finds the IANA time zone of a coordinate, using the smallest zone outline that contains it.
+pub fn lookup(latitude: f64, longitude: f64) -> Tz {
+  let smallest = OUTLINES
+    .lines()
+    .filter_map(outline)
+    .filter(|(_, points)| contains(points, (latitude, longitude)))
+    .map(|(zone, points)| (area(&points), zone))
+    .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    .map(|(_, zone)| zone);
+
+  smallest
+    .or_else(|| nearest(latitude, longitude))
+    .and_then(|zone| zone.parse().ok())
+    .unwrap_or_else(|| nautical(longitude))
+}
+
+fn nearest(latitude: f64, longitude: f64) -> Option<&'static str> {
+  OUTLINES
+    .lines()
+    .filter_map(outline)
+    .map(|(zone, points)| (distance(&points, (latitude, longitude)), zone))
+    .filter(|(distance, _)| *distance <= NEARBY)
+    .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    .map(|(_, zone)| zone)
+}
+
+fn outline(line: &str) -> Option<(&str, Vec<(f64, f64)>)> {
+  if line.starts_with('#') {
+    return None;
+  }
+
+  let mut fields = line.split_whitespace();
+
+  let zone = fields.next()?;
+  let points = fields
+    .map(|field| {
+      let (latitude, longitude) = field.split_once(',')?;
+
+      Some((latitude.parse().ok()?, longitude.parse().ok()?))
+    })
+    .collect::<Option<Vec<_>>>()?;
+
+  Some((zone, points))
+}
+
+// A ray cast along the parallel crosses the outline an odd number of times from inside it.
+fn contains(points: &[(f64, f64)], (latitude, longitude): (f64, f64)) -> bool {
+  let edges = points.iter().zip(points.iter().cycle().skip(1));
+
+  edges
+    .filter(|((a_latitude, a_longitude), (b_latitude, b_longitude))| {
+      (*a_latitude > latitude) != (*b_latitude > latitude)
+        && longitude < a_longitude + (b_longitude - a_longitude) * (latitude - a_latitude) / (b_latitude - a_latitude)
+    })
+    .count()
+    % 2
+    == 1
+}
+
+// Distance to the closest edge in degrees of latitude, with longitudes narrowed towards the poles.
+fn distance(points: &[(f64, f64)], (latitude, longitude): (f64, f64)) -> f64 {
+  let scale = latitude.to_radians().cos();
+  let edges = points.iter().zip(points.iter().cycle().skip(1));
+
+  edges
+    .map(|((a_latitude, a_longitude), (b_latitude, b_longitude))| {
+      let (x, y) = ((a_longitude - longitude) * scale, a_latitude - latitude);
+      let (dx, dy) = ((b_longitude - a_longitude) * scale, b_latitude - a_latitude);
+      let length = dx * dx + dy * dy;
+      let along = if length > 0.0 {
+        (-(x * dx + y * dy) / length).clamp(0.0, 1.0)
+      } else {
+        0.0
+      };
+
+      (x + along * dx).hypot(y + along * dy)
+    })
+    .fold(f64::INFINITY, f64::min)
+}
+
+// Only used to rank overlapping outlines, so square degrees are good enough.
+fn area(points: &[(f64, f64)]) -> f64 {
+  let edges = points.iter().zip(points.iter().cycle().skip(1));
+
+  edges
+    .map(|((a_latitude, a_longitude), (b_latitude, b_longitude))| a_longitude * b_latitude - b_longitude * a_latitude)
+    .sum::<f64>()
+    .abs()
+    / 2.0
+}
+
+// Nautical time zones are one hour wide, and the sign of `Etc/GMT` zones is inverted.
+fn nautical(longitude: f64) -> Tz {
+  let hours = (longitude / 15.0).round() as i32;
+
+  format!("Etc/GMT{:+}", -hours).parse().unwrap_or(Tz::UTC)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn zone(latitude: f64, longitude: f64) -> String {
+    lookup(latitude, longitude).name().to_string()
+  }
+
+  #[test]
+  fn alpine_border_towns() {
+    assert_eq!(zone(45.9237, 6.8694), "Europe/Paris"); // Chamonix
+    assert_eq!(zone(46.1794, 6.7089), "Europe/Paris"); // Morzine
+    assert_eq!(zone(46.2044, 6.1432), "Europe/Zurich"); // Geneva
+    assert_eq!(zone(46.0207, 7.7491), "Europe/Zurich"); // Zermatt
+    assert_eq!(zone(45.7969, 6.9690), "Europe/Rome"); // Courmayeur
+    assert_eq!(zone(46.0037, 8.9511), "Europe/Zurich"); // Lugano
+    assert_eq!(zone(45.8081, 9.0852), "Europe/Rome"); // Como
+    assert_eq!(zone(47.8095, 13.0550), "Europe/Vienna"); // Salzburg
+    assert_eq!(zone(47.8572, 12.9580), "Europe/Berlin"); // Freilassing
+    assert_eq!(zone(47.6300, 13.0042), "Europe/Berlin"); // Berchtesgaden
+    assert_eq!(zone(47.5833, 12.1667), "Europe/Vienna"); // Kufstein
+    assert_eq!(zone(47.6603, 9.1758), "Europe/Berlin"); // Konstanz
+    assert_eq!(zone(47.6501, 9.1750), "Europe/Zurich"); // Kreuzlingen
+  }
+
+  #[test]
+  fn rhine_and_benelux_border_towns() {
+    assert_eq!(zone(48.5734, 7.7521), "Europe/Paris"); // Strasbourg
+    assert_eq!(zone(48.5720, 7.8150), "Europe/Berlin"); // Kehl
+    assert_eq!(zone(50.6292, 3.0573), "Europe/Paris"); // Lille
+    assert_eq!(zone(50.6065, 3.3887), "Europe/Brussels"); // Tournai
+    assert_eq!(zone(50.8514, 5.6910), "Europe/Amsterdam"); // Maastricht
+    assert_eq!(zone(45.6495, 13.7768), "Europe/Rome"); // Trieste
+  }
+
+  #[test]
+  fn great_lakes_border_towns() {
+    assert_eq!(zone(42.3314, -83.0458), "America/Detroit"); // Detroit
+    assert_eq!(zone(42.3149, -83.0364), "America/Toronto"); // Windsor
+    assert_eq!(zone(46.4953, -84.3453), "America/Detroit"); // Sault Ste. Marie, Michigan
+    assert_eq!(zone(46.5219, -84.3461), "America/Toronto"); // Sault Ste. Marie, Ontario
+    assert_eq!(zone(42.8864, -78.8784), "America/New_York"); // Buffalo
+  }
+
+  #[test]
+  fn shores_outside_outlines() {
+    assert_eq!(zone(-4.7692, 11.8664), "Africa/Brazzaville"); // Pointe-Noire
+    assert_eq!(zone(10.3910, -75.4794), "America/Bogota"); // Cartagena
+    assert_eq!(zone(0.0, -30.0), "Etc/GMT+2");
+  }
+}
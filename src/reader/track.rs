@@ -0,0 +1,210 @@
+use std::path::Path;
+
+use chrono::{Duration, NaiveDateTime};
+
+use super::{
+  gpmf::{types::Gps5, Gpmf},
+  mp4::Mp4,
+};
+use crate::{
+  error::Result,
+  ui::state::session::{Bounds, Profile, Stats},
+  utils,
+};
+
+// A valid GPS fix of a clip.
+pub struct Point {
+  pub latitude: f64,
+  pub longitude: f64,
+  pub altitude: f64,
+  pub speed_2d: f64,
+
+  // seconds since the start of the clip.
+  pub time: f64,
+
+  // the UTC time of the fix, if the camera had a GPS time.
+  pub utc: Option<NaiveDateTime>,
+}
+
+impl Point {
+  fn from(gps5: &Gps5, utc: Option<NaiveDateTime>) -> Self {
+    Self {
+      latitude: gps5.latitude,
+      longitude: gps5.longitude,
+      altitude: gps5.altitude,
+      speed_2d: gps5.speed_2d,
+      time: gps5.time,
+      utc,
+    }
+  }
+}
+
+// Reads every valid GPS fix of a clip.
+pub fn read(path: &Path) -> Result<Vec<Point>> {
+  let mut mp4 = Mp4::open(path)?;
+  let mut fixes = Fixes::default();
+
+  for payload in mp4.gpmf_payloads()?.recovering() {
+    fixes.add(&payload?);
+  }
+
+  Ok(fixes.points())
+}
+
+// The valid GPS fixes of a clip, collected one payload at a time.
+#[derive(Default)]
+pub struct Fixes {
+  gps9: Vec<Point>,
+  gps5: Vec<Point>,
+}
+
+impl Fixes {
+  pub fn add(&mut self, payload: &[Gpmf]) {
+    collect(payload, &mut self.gps9, &mut self.gps5);
+  }
+
+  // GPS9 samples are preferred over GPS5 when a clip has both, since they carry their own time.
+  pub fn points(self) -> Vec<Point> {
+    if self.gps9.is_empty() {
+      self.gps5
+    } else {
+      self.gps9
+    }
+  }
+}
+
+// Altitude changes smaller than this many meters are considered GPS noise.
+const ELEVATION_THRESHOLD: f64 = 3.0;
+
+pub fn stats(points: &[Point]) -> Option<Stats> {
+  let first = points.first()?;
+
+  let mut stats = Stats {
+    distance: 0.0,
+    max_speed: 0.0,
+    avg_speed: points.iter().map(|point| point.speed_2d).sum::<f64>() / points.len() as f64,
+    elevation_gain: 0.0,
+    elevation_loss: 0.0,
+    bounds: Bounds {
+      min_latitude: first.latitude,
+      min_longitude: first.longitude,
+      max_latitude: first.latitude,
+      max_longitude: first.longitude,
+    },
+  };
+
+  let mut reference_altitude = first.altitude;
+
+  for (i, point) in points.iter().enumerate() {
+    if i > 0 {
+      let previous = &points[i - 1];
+      stats.distance += utils::distance((previous.latitude, previous.longitude), (point.latitude, point.longitude));
+    }
+
+    stats.max_speed = stats.max_speed.max(point.speed_2d);
+
+    let climb = point.altitude - reference_altitude;
+    if climb.abs() >= ELEVATION_THRESHOLD {
+      if climb > 0.0 {
+        stats.elevation_gain += climb;
+      } else {
+        stats.elevation_loss -= climb;
+      }
+
+      reference_altitude = point.altitude;
+    }
+
+    stats.bounds.min_latitude = stats.bounds.min_latitude.min(point.latitude);
+    stats.bounds.min_longitude = stats.bounds.min_longitude.min(point.longitude);
+    stats.bounds.max_latitude = stats.bounds.max_latitude.max(point.latitude);
+    stats.bounds.max_longitude = stats.bounds.max_longitude.max(point.longitude);
+  }
+
+  Some(stats)
+}
+
+// Averages speed and altitude over `Profile::SAMPLES` equal slices of a clip lasting `seconds`.
+// Slices without fixes repeat the previous slice.
+pub fn profile(points: &[Point], seconds: f64) -> Option<Profile> {
+  if points.is_empty() || seconds <= 0.0 {
+    return None;
+  }
+
+  let mut sums = [(0.0, 0.0, 0); Profile::SAMPLES];
+  for point in points {
+    let slice = ((point.time / seconds * Profile::SAMPLES as f64) as usize).min(Profile::SAMPLES - 1);
+
+    sums[slice].0 += point.speed_2d;
+    sums[slice].1 += point.altitude;
+    sums[slice].2 += 1;
+  }
+
+  let mut previous = (points[0].speed_2d, points[0].altitude);
+  let mut profile = Profile {
+    speed: Vec::with_capacity(Profile::SAMPLES),
+    altitude: Vec::with_capacity(Profile::SAMPLES),
+  };
+
+  for (speed, altitude, count) in sums {
+    if count > 0 {
+      previous = (speed / count as f64, altitude / count as f64);
+    }
+
+    profile.speed.push(previous.0);
+    profile.altitude.push(previous.1);
+  }
+
+  Some(profile)
+}
+
+// Routes are downsampled to at most this many fixes, enough to draw them in a terminal.
+const ROUTE_POINTS: usize = 256;
+
+// The `(latitude, longitude)` of evenly spaced fixes of a clip, always including the last one.
+pub fn route(points: &[Point]) -> Vec<(f64, f64)> {
+  let step = points.len().div_ceil(ROUTE_POINTS).max(1);
+
+  let mut route: Vec<_> = points.iter().step_by(step).map(|point| (point.latitude, point.longitude)).collect();
+  if let Some(last) = points.last() {
+    if !(points.len() - 1).is_multiple_of(step) {
+      route.push((last.latitude, last.longitude));
+    }
+  }
+
+  route
+}
+
+fn collect(gpmf: &[Gpmf], gps9: &mut Vec<Point>, gps5: &mut Vec<Point>) {
+  // GPS5 samples of a stream are timed relative to the stream's GPSU.
+  let gpsu = gpmf.iter().find_map(|entry| match entry {
+    Gpmf::GpsTime { time } => Some(*time),
+    _ => None,
+  });
+
+  for entry in gpmf {
+    match entry {
+      Gpmf::Klv { entries, .. } => collect(entries, gps9, gps5),
+
+      Gpmf::Gps9 { entries } => gps9.extend(
+        entries
+          .iter()
+          .filter(|sample| sample.is_valid())
+          .map(|sample| Point::from(&sample.gps5(), Some(sample.utc))),
+      ),
+
+      Gpmf::Gps5 { entries } => {
+        let start = entries.first().map_or(0.0, |sample| sample.time);
+        let utc = |sample: &Gps5| gpsu.map(|gpsu| gpsu + Duration::milliseconds(((sample.time - start) * 1000.0).round() as i64));
+
+        gps5.extend(
+          entries
+            .iter()
+            .filter(|sample| sample.is_valid())
+            .map(|sample| Point::from(sample, utc(sample))),
+        );
+      }
+
+      _ => (),
+    }
+  }
+}
@@ -10,6 +10,7 @@ macro_rules! colors {
         Color::Gray => Color::White,
         Color::Yellow => Color::LightYellow,
         Color::Blue => Color::LightBlue,
+        Color::Cyan => Color::LightCyan,
         color => color,
       }
     }
@@ -44,10 +45,14 @@ macro_rules! colors {
 
 colors! {
  date: Color::Magenta,
+ place: Color::Gray,
  count: Color::Gray,
  size: Color::Yellow,
  duration: Color::Green,
+ stats: Color::Cyan,
+ highlights: Color::Yellow,
  filename: Color::Gray,
+ sidecars: Color::DarkGray,
  destination: Color::Blue,
  status_import: Color::Green,
  status_ignore: Color::Red,
@@ -62,4 +67,8 @@ colors! {
  error_block: Color::Red,
 
  progress: Color::Green,
+
+ map_track: Color::Blue,
+ map_selected: Color::Yellow,
+ map_outline: Color::DarkGray,
 }
@@ -1,6 +1,6 @@
 use std::{path::PathBuf, sync::mpsc::Sender, thread, time::Duration};
 
-use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyEvent};
 
 use super::state::{destination::Destination, session::File};
 use crate::{
@@ -9,11 +9,12 @@ use crate::{
 };
 
 pub enum Event {
-  Key { code: KeyCode, modifiers: KeyModifiers },
+  Key { code: KeyCode },
   File(Box<File>),
   Destination(Destination),
   DestinationSession { path: PathBuf },
   Error(String),
+  Message(String),
   Tick,
 }
 
@@ -38,7 +39,7 @@ const TICK_RATE: Duration = Duration::from_millis(50);
 fn event_tick() -> Result<Event> {
   let event = if event::poll(TICK_RATE)? {
     match event::read()? {
-      CrosstermEvent::Key(KeyEvent { code, modifiers }) => Event::Key { code, modifiers },
+      CrosstermEvent::Key(KeyEvent { code, .. }) => Event::Key { code },
       _ => Event::Tick,
     }
   } else {
@@ -74,6 +74,12 @@ impl Ui {
         (Focus::Sessions, Popup::None, Event::Key { code: Char('n'), .. }) => self.state.search(),
         (Focus::Sessions, Popup::None, Event::Key { code: Char('I'), .. }) => self.state.import(),
 
+        (_, Popup::None, Event::Key { code: Char('e'), .. }) => self.state.export(self.event_channel.sender()),
+        (_, Popup::None, Event::Key { code: Char('E'), .. }) => self.state.next_export_format(),
+        (_, Popup::None, Event::Key { code: Char('p'), .. }) => self.state.toggle_sparkline(),
+        (_, Popup::None, Event::Key { code: Char('m'), .. }) => self.state.toggle_map(),
+        (_, Popup::None, Event::Key { code: Char('o'), .. }) => self.state.toggle_map_outline(),
+
         (Focus::Files, Popup::None, Event::Key { code: Char('a'), .. }) => {
           self.state.toggle_file_import();
           self.update_file_cache()?;
@@ -88,6 +94,11 @@ impl Ui {
             self.event_channel.sender.send(Event::Error(format!("{:?}", error)))?;
           }
         }
+        (Focus::Files, Popup::None, Event::Key { code: Char('H'), .. }) => {
+          if let Err(error) = self.state.next_highlight() {
+            self.event_channel.sender.send(Event::Error(format!("{:?}", error)))?;
+          }
+        }
 
         (_, Popup::Search, Event::Key { code: Char(c), .. }) => self.state.search_char(c),
         (_, Popup::Search, Event::Key { code: Backspace, .. }) => self.state.search_del(),
@@ -121,6 +132,7 @@ impl Ui {
         }
 
         (_, _, Event::Error(error)) => self.state.error(error),
+        (_, _, Event::Message(message)) => self.state.status(message),
 
         (_, _, Event::Tick) => self.state.sync(),
 
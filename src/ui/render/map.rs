@@ -0,0 +1,109 @@
+use tui::{
+  layout::Rect,
+  symbols::Marker,
+  widgets::{
+    canvas::{Canvas, Context, Line, Map, MapResolution, Points},
+    Block, Borders,
+  },
+};
+
+use crate::ui::{colors::Colors, state::State};
+
+// Fraction of the session's extent added around its tracks.
+const PADDING: f64 = 0.1;
+
+// Plots the GPS tracks of every file of the selected session, with the selected file's track
+// highlighted.
+pub fn map(state: &State, area: Rect) -> Canvas<'_, impl Fn(&mut Context) + '_> {
+  let ([x_min, x_max], [y_min, y_max]) = bounds(state, area);
+
+  let colors = Colors::normal();
+  let title = state.session().map_or_else(String::new, |session| format!("Map {}", session.date));
+
+  Canvas::default()
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .marker(Marker::Braille)
+    .x_bounds([x_min, x_max])
+    .y_bounds([y_min, y_max])
+    .paint(move |ctx| {
+      if state.map_outline {
+        ctx.draw(&Map {
+          resolution: MapResolution::High,
+          color: colors.map_outline,
+        });
+        ctx.layer();
+      }
+
+      let Some(session) = state.session() else {
+        return;
+      };
+
+      // the selected track is drawn last, so that it's on top.
+      let mut files: Vec<_> = session.files.values().enumerate().collect();
+      files.sort_by_key(|(i, _)| *i == state.file_idx);
+
+      for (i, file) in files {
+        let color = if i == state.file_idx {
+          colors.map_selected
+        } else {
+          colors.map_track
+        };
+
+        let route = file.recording_route();
+
+        // a route with a single fix has no segments.
+        if let [(y, x)] = route.as_slice() {
+          ctx.draw(&Points {
+            coords: &[(*x, *y)],
+            color,
+          });
+        }
+
+        for segment in route.windows(2) {
+          let [(y1, x1), (y2, x2)] = [segment[0], segment[1]];
+          ctx.draw(&Line { x1, y1, x2, y2, color });
+        }
+      }
+    })
+}
+
+// The longitude and latitude bounds of the selected session's tracks, padded and widened to
+// the aspect ratio of `area` so tracks aren't stretched. Sessions without GPS data show the
+// whole world.
+fn bounds(state: &State, area: Rect) -> ([f64; 2], [f64; 2]) {
+  let bounds = match state.session().and_then(|session| session.stats()) {
+    Some(stats) => stats.bounds,
+    None => return ([-180.0, 180.0], [-90.0, 90.0]),
+  };
+
+  let (x_center, y_center) = (
+    (bounds.min_longitude + bounds.max_longitude) / 2.0,
+    (bounds.min_latitude + bounds.max_latitude) / 2.0,
+  );
+
+  // a degree of longitude shrinks towards the poles.
+  let x_scale = y_center.to_radians().cos().max(0.01);
+
+  // braille cells are 2 dots wide and 4 dots tall.
+  let aspect = f64::from(area.width.max(1) * 2) / f64::from(area.height.max(1) * 4);
+
+  let mut width = (bounds.max_longitude - bounds.min_longitude) * x_scale * (1.0 + 2.0 * PADDING);
+  let mut height = (bounds.max_latitude - bounds.min_latitude) * (1.0 + 2.0 * PADDING);
+
+  // tracks of a single point still get some surroundings.
+  width = width.max(0.001);
+  height = height.max(0.001);
+
+  if width / height > aspect {
+    height = width / aspect;
+  } else {
+    width = height * aspect;
+  }
+
+  let (half_width, half_height) = (width / x_scale / 2.0, height / 2.0);
+
+  (
+    [x_center - half_width, x_center + half_width],
+    [y_center - half_height, y_center + half_height],
+  )
+}
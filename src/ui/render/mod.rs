@@ -1,3 +1,4 @@
+pub mod map;
 pub mod rows;
 pub mod search;
 pub mod sections;
@@ -1,4 +1,4 @@
-use std::{os::unix::fs::MetadataExt, path::PathBuf};
+use std::{collections::BTreeSet, path::PathBuf};
 
 use tui::{
   style::{Color, Modifier, Style},
@@ -12,7 +12,8 @@ use crate::{
     colors::Colors,
     state::{
       focus::Focus,
-      session::{File, Session, Status},
+      session::{File, Session, Stats, Status},
+      sparkline::Sparkline,
       State,
     },
   },
@@ -30,59 +31,94 @@ impl<'a> Rowable<'a> for Session {
 
     let (import_size, uncategorized_size) = size_split(self);
 
-    vec![
+    let mut row = vec![
       Spans::from(Span::styled(
         self.date.clone(),
         Style::default().fg(colors.date).add_modifier(modifier),
       )),
+      Spans::from(Span::styled(
+        self.place.as_ref().map_or_else(String::new, ToString::to_string),
+        Style::default().fg(colors.place).add_modifier(modifier),
+      )),
       human_readable_file_counts(self.files.values(), colors.count, colors.status_import),
       Spans::from(Span::styled(
-        human_readable_size(self.files.values().map(|f| f.metadata.size()).sum::<u64>()),
+        human_readable_size(self.files.values().map(File::recording_size).sum::<u64>()),
         Style::default().fg(colors.size).add_modifier(modifier),
       )),
       human_readable_size_split(import_size, uncategorized_size, colors.status_import, colors.status_none),
-      Spans::from(Span::styled(
-        self.destination.as_ref().map_or("".to_string(), |d| format!("-> {}", &d.rel)),
-        Style::default().fg(colors.destination).add_modifier(modifier),
-      )),
-    ]
+    ];
+
+    row.extend(stats_columns(
+      self.stats().as_ref(),
+      Style::default().fg(colors.stats).add_modifier(modifier),
+    ));
+
+    row.push(Spans::from(Span::styled(
+      self.destination.as_ref().map_or("".to_string(), |d| format!("-> {}", &d.rel)),
+      Style::default().fg(colors.destination).add_modifier(modifier),
+    )));
+
+    row
   }
 }
 
-impl<'a> Rowable<'a> for File {
+struct FileRow<'b> {
+  file: &'b File,
+
+  // the file's sparkline, scaled to the other files of its session.
+  sparkline: String,
+}
+
+impl<'a, 'b> Rowable<'a> for FileRow<'b> {
   fn row(&self, selected: bool, focused: bool) -> Vec<Spans<'a>> {
+    let FileRow { file, sparkline } = self;
+
     let modifier = if selected { Modifier::BOLD } else { Modifier::empty() };
 
     let colors = Colors::focused(selected && focused);
 
-    let (status, status_color) = match self.status {
+    let (status, status_color) = match file.status {
       None => (" ", colors.status_none),
       Some(Status::Import) => ("+", colors.status_import),
       Some(Status::Ignore) => ("-", colors.status_ignore),
     };
 
-    vec![
+    let mut row = vec![
       Spans::from(Span::styled(
         format!(" {}", status),
         Style::default().fg(status_color).add_modifier(modifier),
       )),
       Spans::from(Span::styled(
-        self.path.file_name().unwrap().to_string_lossy().into_owned(),
+        file_name(file),
         Style::default().fg(colors.filename).add_modifier(modifier),
       )),
+      Spans::from(Span::styled(sidecars(file), Style::default().fg(colors.sidecars))),
       Spans::from(Span::styled(
-        human_readable_size(self.metadata.len()),
+        human_readable_size(file.recording_size()),
         Style::default().fg(colors.size).add_modifier(modifier),
       )),
       Spans::from(Span::styled(
-        human_readable_seconds(self.seconds as i64),
+        human_readable_seconds(file.recording_seconds() as i64),
         Style::default().fg(colors.duration).add_modifier(modifier),
       )),
+      Spans::from(Span::styled(sparkline.clone(), Style::default().fg(colors.stats))),
       Spans::from(Span::styled(
-        self.note.clone().unwrap_or_else(|| "".to_string()),
-        Style::default().fg(colors.filename).add_modifier(modifier),
+        human_readable_highlights(file.recording().map(|chapter| chapter.highlights.len()).sum()),
+        Style::default().fg(colors.highlights).add_modifier(modifier),
       )),
-    ]
+    ];
+
+    row.extend(stats_columns(
+      file.recording_stats().as_ref(),
+      Style::default().fg(colors.stats).add_modifier(modifier),
+    ));
+
+    row.push(Spans::from(Span::styled(
+      file.note.clone().unwrap_or_default(),
+      Style::default().fg(colors.filename).add_modifier(modifier),
+    )));
+
+    row
   }
 }
 
@@ -110,18 +146,42 @@ pub fn sessions(state: &State) -> Vec<Vec<Spans<'_>>> {
     .sessions
     .iter()
     .enumerate()
-    .map(|(i, (_, s))| (s.row(i == state.session_idx, state.focus == Focus::Sessions)))
+    .map(|(i, (_, s))| s.row(i == state.session_idx, state.focus == Focus::Sessions))
     .collect()
 }
 
 pub fn files(state: &State) -> Vec<Vec<Spans<'_>>> {
   match state.session() {
     None => vec![],
-    Some(Session { files, .. }) => files
-      .values()
-      .enumerate()
-      .map(|(i, f)| (f.row(i == state.file_idx, state.focus == Focus::Files)))
-      .collect(),
+    Some(Session { files, .. }) => {
+      let values = |file: &File| -> Option<Vec<f64>> {
+        let profile = file.recording_profile()?;
+
+        match state.sparkline {
+          Sparkline::Speed => Some(profile.speed),
+          Sparkline::Altitude => Some(profile.altitude),
+          Sparkline::Hidden => None,
+        }
+      };
+
+      // speeds are scaled from zero, altitudes from the lowest altitude of the session.
+      let all_values: Vec<f64> = files.values().filter_map(values).flatten().collect();
+      let max = all_values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+      let min = match state.sparkline {
+        Sparkline::Speed => 0.0,
+        _ => all_values.iter().copied().fold(f64::INFINITY, f64::min),
+      };
+
+      files
+        .values()
+        .enumerate()
+        .map(|(i, file)| {
+          let sparkline = values(file).map_or_else(String::new, |values| sparkline(&values, min, max));
+
+          FileRow { file, sparkline }.row(i == state.file_idx, state.focus == Focus::Files)
+        })
+        .collect()
+    }
   }
 }
 
@@ -172,7 +232,9 @@ pub fn destinations(state: &State) -> Vec<Vec<Spans<'_>>> {
   let mut rows = Vec::new();
   let mut stack = Vec::new();
   let sessions_by_destination = state.new_destination_sessions();
-  let selected_session = state.session().map_or_else(|| "".to_string(), |s| s.date.clone());
+  let selected_session = state
+    .session()
+    .map_or_else(|| "".to_string(), |s| s.dir_name(state.mode.session_dir()));
 
   if let Some(destinations) = state.destinations.get(output_dir) {
     extend_stack(
@@ -250,10 +312,15 @@ fn size_split(session: &Session) -> (u64, u64) {
     .files
     .values()
     .filter(|f| f.status == Some(Status::Import))
-    .map(|f| f.metadata.size())
+    .map(File::recording_size)
     .sum();
 
-  let uncategorized: u64 = session.files.values().filter(|f| f.status == None).map(|f| f.metadata.size()).sum();
+  let uncategorized: u64 = session
+    .files
+    .values()
+    .filter(|f| f.status.is_none())
+    .map(File::recording_size)
+    .sum();
 
   (imported, uncategorized)
 }
@@ -321,6 +388,71 @@ fn human_readable_size(size: u64) -> String {
   }
 }
 
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+fn sparkline(values: &[f64], min: f64, max: f64) -> String {
+  let range = max - min;
+
+  values
+    .iter()
+    .map(|value| {
+      let level = if range > 0.0 { (value - min) / range } else { 0.0 };
+      SPARKS[((level * (SPARKS.len() - 1) as f64).round() as usize).min(SPARKS.len() - 1)]
+    })
+    .collect()
+}
+
+// Distance, average and max speed, and elevation gain and loss columns, empty without GPS data.
+fn stats_columns(stats: Option<&Stats>, style: Style) -> [Spans<'static>; 3] {
+  let columns = match stats {
+    None => Default::default(),
+    Some(stats) => [
+      human_readable_distance(stats.distance),
+      format!("{:.0}/{:.0} km/h", stats.avg_speed * 3.6, stats.max_speed * 3.6),
+      format!("+{:.0}/-{:.0} m", stats.elevation_gain, stats.elevation_loss),
+    ],
+  };
+
+  columns.map(|column| Spans::from(Span::styled(column, style)))
+}
+
+fn human_readable_distance(meters: f64) -> String {
+  if meters < 1_000.0 {
+    format!("{:.0} m", meters)
+  } else {
+    format!("{:.1} km", meters / 1_000.0)
+  }
+}
+
+// The name of the first chapter of a recording, with the number of chapters that follow it.
+fn file_name(file: &File) -> String {
+  let name = file.path.file_name().unwrap().to_string_lossy().into_owned();
+
+  match file.chapters.len() {
+    0 => name,
+    chapters => format!("{} +{}", name, chapters),
+  }
+}
+
+// The kinds of sidecar files of a recording, e.g. `LRV THM`.
+fn sidecars(file: &File) -> String {
+  file
+    .recording()
+    .flat_map(|chapter| chapter.sidecars.iter().map(|sidecar| sidecar.kind))
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .map(|kind| kind.extension())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn human_readable_highlights(count: usize) -> String {
+  match count {
+    0 => String::new(),
+    count => format!("★{}", count),
+  }
+}
+
 fn human_readable_seconds(seconds: i64) -> String {
   let secs = seconds % 60;
   let mins = (seconds / 60) % 60;
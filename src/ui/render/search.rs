@@ -6,7 +6,7 @@ pub struct Match<'a> {
   pub score: f64,
 }
 
-pub fn score<S: AsRef<str>>(search: S, destination: &Destination) -> Option<Match> {
+pub fn score<S: AsRef<str>>(search: S, destination: &Destination) -> Option<Match<'_>> {
   rff::match_and_score_with_positions(search.as_ref(), destination.rel.as_ref()).map(|(_, score, positions)| Match {
     destination,
     positions,
@@ -3,7 +3,7 @@ use std::io::Stdout;
 use tui::{
   backend::CrosstermBackend,
   layout::{Constraint, Direction, Layout, Rect},
-  style::Style,
+  style::{Color, Style},
   text::Span,
   widgets::{Block, Borders, Clear, Gauge, Paragraph, TableState, Wrap},
   Frame,
@@ -11,7 +11,7 @@ use tui::{
 
 use super::{
   super::state::{focus::Focus, State},
-  rows,
+  map, rows,
   table::Table,
 };
 use crate::ui::{colors::Colors, state::progress::Bare as BareProgress};
@@ -68,11 +68,16 @@ impl<'a> Sections<'a> {
 
     self.render_sessions(frame);
     self.render_files(frame);
-    self.render_destinations(frame);
+
+    if self.state.map {
+      frame.render_widget(map::map(self.state, self.destinations), self.destinations);
+    } else {
+      self.render_destinations(frame);
+    }
 
     if let Some(error) = &self.state.error {
       frame.render_widget(Clear, self.popup);
-      frame.render_widget(self.popup(error.clone()), self.popup);
+      frame.render_widget(self.popup("Error", error.clone(), Colors::normal().error_block), self.popup);
     } else if let Some(input) = &self.state.input {
       frame.render_widget(Clear, self.input);
       frame.render_widget(self.input(input.clone()), self.input);
@@ -102,7 +107,7 @@ impl<'a> Sections<'a> {
       Table::new(rows::sessions(self.state))
         .title("Sessions")
         .focused(self.state.focus == Focus::Sessions)
-        .alignments([Left, Left, Right, Right, Left]),
+        .alignments([Left, Left, Left, Right, Right, Right, Right, Right, Left]),
       self.sessions,
       &mut self.sessions_state(),
     );
@@ -111,11 +116,17 @@ impl<'a> Sections<'a> {
   fn render_files(&self, frame: &mut Frame<CrosstermBackend<Stdout>>) {
     use super::table::Alignment::{Left, Right};
 
+    let format = self.state.export_format.extension();
+    let title = match &self.state.status {
+      Some(status) => format!("Files · {} · {}", format, status),
+      None => format!("Files · {}", format),
+    };
+
     frame.render_stateful_widget(
       Table::new(rows::files(self.state))
-        .title("Files")
+        .title(title)
         .focused(self.state.focus == Focus::Files)
-        .alignments([Left, Left, Right, Right, Left]),
+        .alignments([Left, Left, Left, Right, Right, Left, Right, Right, Right, Right, Left]),
       self.files,
       &mut self.files_state(),
     );
@@ -127,7 +138,7 @@ impl<'a> Sections<'a> {
     frame.render_widget(Table::new(rows::destinations(self.state)).title(title), self.destinations);
   }
 
-  fn input(&self, input: String) -> Paragraph {
+  fn input(&self, input: String) -> Paragraph<'_> {
     Paragraph::new(Span::raw(input))
       .block(
         Block::default()
@@ -138,7 +149,7 @@ impl<'a> Sections<'a> {
       .style(Style::default().fg(Colors::normal().input_text))
   }
 
-  fn search(&self, input: String) -> Paragraph {
+  fn search(&self, input: String) -> Paragraph<'_> {
     Paragraph::new(Span::raw(input))
       .block(
         Block::default()
@@ -154,7 +165,7 @@ impl<'a> Sections<'a> {
     frame.render_widget(Table::new(rows::search_matches(self.state, input)), self.search_results);
   }
 
-  fn progress(&self, progress: &BareProgress) -> Gauge {
+  fn progress(&self, progress: &BareProgress) -> Gauge<'_> {
     Gauge::default()
       .block(
         Block::default()
@@ -166,10 +177,10 @@ impl<'a> Sections<'a> {
       .percent((progress.file_idx * 100 / progress.file_total) as u16)
   }
 
-  fn popup(&self, error: String) -> Paragraph {
-    Paragraph::new(error)
-      .block(Block::default().title("Error").borders(Borders::ALL))
-      .style(Style::default().fg(Colors::normal().error_block))
+  fn popup(&self, title: &'static str, text: String, color: Color) -> Paragraph<'_> {
+    Paragraph::new(text)
+      .block(Block::default().title(title).borders(Borders::ALL))
+      .style(Style::default().fg(color))
       .wrap(Wrap { trim: true })
   }
 
@@ -54,7 +54,7 @@ impl<'a> Table<'a> {
       .rows
       .iter()
       .map(|row| row.iter().map(Spans::width).collect::<Vec<usize>>())
-      .reduce(|row1, row2| row1.into_iter().zip(row2.into_iter()).map(|(a, b)| cmp::max(a, b)).collect())
+      .reduce(|row1, row2| row1.into_iter().zip(row2).map(|(a, b)| cmp::max(a, b)).collect())
       .unwrap_or_default()
       .into_iter()
       .map(|width| Constraint::Length(width as u16))
@@ -1,11 +1,6 @@
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Default)]
 pub enum Focus {
+  #[default]
   Sessions,
   Files,
 }
-
-impl Default for Focus {
-  fn default() -> Self {
-    Self::Sessions
-  }
-}
@@ -2,6 +2,7 @@ pub mod destination;
 pub mod focus;
 pub mod progress;
 pub mod session;
+pub mod sparkline;
 
 use std::{
   collections::{BTreeMap, BTreeSet},
@@ -13,10 +14,20 @@ use self::{
   destination::Destination,
   focus::Focus,
   progress::Progress,
-  session::{Date, File, Session, Status},
+  session::{Date, File, Session, Status, UNKNOWN_DATE},
+  sparkline::Sparkline,
 };
 use super::{events::Event, render::search};
-use crate::{error::Result, mode::Mode, mpv::Player, writer::Writer};
+use crate::{
+  error::Result,
+  export::{self, Format},
+  mode::Mode,
+  mpv::Player,
+  writer::Writer,
+};
+
+// seconds a HiLight tag must be ahead of the playback position to be jumped to.
+const HIGHLIGHT_MARGIN: f64 = 1.0;
 
 pub struct State {
   pub mode: Mode,
@@ -26,6 +37,15 @@ pub struct State {
   pub search: Option<String>,
   pub error: Option<String>,
   pub progress: Option<Progress>,
+  pub export_format: Format,
+  // a note on the last action, shown with the export format in the files' title until the
+  // selection moves.
+  pub status: Option<String>,
+  pub sparkline: Sparkline,
+
+  // whether the map replaces the destinations, and whether it shows coastlines.
+  pub map: bool,
+  pub map_outline: bool,
 
   pub sessions: BTreeMap<Date, Session>,
   pub destinations: BTreeMap<PathBuf, BTreeSet<Destination>>,
@@ -48,6 +68,12 @@ impl State {
       search: None,
       error: None,
       progress: None,
+      export_format: Format::default(),
+      status: None,
+      sparkline: Sparkline::default(),
+
+      map: false,
+      map_outline: true,
 
       sessions: BTreeMap::new(),
       destinations: BTreeMap::new(),
@@ -97,7 +123,7 @@ impl State {
         continue;
       };
 
-      let session_destination_path = destination_path.join(session.date.clone());
+      let session_destination_path = destination_path.join(session.dir_name(self.mode.session_dir()));
 
       if let Some(sessions) = destination_sessions.get_mut(destination_path) {
         sessions.insert(session_destination_path);
@@ -154,21 +180,21 @@ impl State {
 
   pub fn toggle_file_import(&mut self) {
     if let Some(file) = self.file_mut() {
-      file.status = if file.status == Some(Status::Import) {
+      file.set_status(if file.status == Some(Status::Import) {
         None
       } else {
         Some(Status::Import)
-      }
+      })
     }
   }
 
   pub fn toggle_file_ignore(&mut self) {
     if let Some(file) = self.file_mut() {
-      file.status = if file.status == Some(Status::Ignore) {
+      file.set_status(if file.status == Some(Status::Ignore) {
         None
       } else {
         Some(Status::Ignore)
-      }
+      })
     }
   }
 
@@ -195,7 +221,9 @@ impl State {
   }
 
   pub fn import(&mut self) {
-    let progress = self.writer.spawn(self.sessions.clone().into_values().collect());
+    let progress = self
+      .writer
+      .spawn(self.sessions.clone().into_values().collect(), self.mode.session_dir());
     self.progress = Some(progress);
   }
 
@@ -215,7 +243,7 @@ impl State {
 
   pub fn set_session_destination(&mut self) {
     if let Some(search) = self.search.clone() {
-      let destination = if let Some(search_match) = search::sorted(search, self.destinations()).get(0) {
+      let destination = if let Some(search_match) = search::sorted(search, self.destinations()).first() {
         search_match.destination.clone()
       } else {
         return;
@@ -233,6 +261,54 @@ impl State {
     self.error = Some(error);
   }
 
+  pub fn status(&mut self, status: String) {
+    self.status = Some(status);
+  }
+
+  // Exports the track of the selected file, or of every file of the selected session, into the
+  // export directory or else the directory the session is imported into.
+  pub fn export(&mut self, event_sender: Sender<Event>) {
+    let session = match self.session() {
+      Some(session) => session,
+      None => return,
+    };
+
+    let (name, files) = match (&self.focus, self.file()) {
+      (Focus::Files, Some(file)) => (
+        file.path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        vec![file.clone()],
+      ),
+      (Focus::Sessions, _) if session.date == UNKNOWN_DATE => ("undated".to_string(), session.files.values().cloned().collect()),
+      (Focus::Sessions, _) => (session.date.clone(), session.files.values().cloned().collect()),
+      _ => return,
+    };
+
+    let dir = match (self.mode.export_dir(), &session.destination) {
+      (Some(dir), _) => dir,
+      (None, Some(destination)) => destination.abs.join(session.dir_name(self.mode.session_dir())),
+      (None, None) => return self.error("Choose a destination for the session, or pass --export-dir".to_string()),
+    };
+
+    export::spawn(dir, name, files, self.export_format, event_sender);
+  }
+
+  pub fn next_export_format(&mut self) {
+    self.export_format = self.export_format.next();
+    self.status = None;
+  }
+
+  pub fn toggle_sparkline(&mut self) {
+    self.sparkline = self.sparkline.next();
+  }
+
+  pub fn toggle_map(&mut self) {
+    self.map = !self.map;
+  }
+
+  pub fn toggle_map_outline(&mut self) {
+    self.map_outline = !self.map_outline;
+  }
+
   pub fn toggle_focus(&mut self) {
     self.focus = match self.focus {
       Focus::Files => Focus::Sessions,
@@ -249,6 +325,38 @@ impl State {
     Ok(())
   }
 
+  // Seeks to the first HiLight tag of the selected recording after the playback position, wrapping
+  // around to its first tag. The recording is played if it isn't already.
+  pub fn next_highlight(&mut self) -> Result<()> {
+    let highlights = match self.file() {
+      Some(file) => file.recording_highlights(),
+      None => return Ok(()),
+    };
+
+    if highlights.is_empty() {
+      self.status("no HiLight tags in this file".to_string());
+      return Ok(());
+    }
+
+    let position = match self.player.playlist_pos() {
+      Some(idx) if idx == self.file_idx => self.player.time_pos(),
+      _ => {
+        if let Some(session) = self.session().cloned() {
+          self.player.load_session(&session)?;
+        }
+
+        None
+      }
+    };
+
+    // skip tags just behind the playback position, so that repeated presses move forward.
+    let next = position
+      .and_then(|position| highlights.iter().find(|highlight| **highlight > position + HIGHLIGHT_MARGIN))
+      .unwrap_or(&highlights[0]);
+
+    self.player.play_at(self.file_idx, *next)
+  }
+
   pub fn escape(&mut self) {
     self.input = None;
     self.search = None;
@@ -262,6 +370,7 @@ impl State {
     };
 
     self.clamp_idxs();
+    self.status = None;
 
     self.update_player().ok();
   }
@@ -273,6 +382,7 @@ impl State {
     };
 
     self.clamp_idxs();
+    self.status = None;
 
     self.update_player().ok();
   }
@@ -301,7 +411,7 @@ impl State {
   pub fn write_note(&mut self) {
     if let Some(input) = self.input.clone() {
       if let Some(ref mut file) = self.file_mut() {
-        file.note = if input.is_empty() { None } else { Some(input) };
+        file.set_note(if input.is_empty() { None } else { Some(input) });
       }
     };
 
@@ -330,6 +440,10 @@ impl State {
       self.file_idx = idx;
     }
 
+    if let Err(error) = self.player.sync() {
+      self.error(format!("seek: {}", error));
+    }
+
     if let Some(progress) = &self.progress {
       if progress.bare().unwrap().done {
         self.progress = None;
@@ -16,14 +16,14 @@ impl Progress {
 
   pub fn set_file_idx(&mut self, file_idx: usize) -> Result<()> {
     let mut inner = self.inner.lock().map_err(|error| err!("lock: {}", error))?;
-    (*inner).file_idx = file_idx;
+    inner.file_idx = file_idx;
 
     Ok(())
   }
 
   pub fn set_done(&mut self) -> Result<()> {
     let mut inner = self.inner.lock().map_err(|error| err!("lock: {}", error))?;
-    (*inner).done = true;
+    inner.done = true;
 
     Ok(())
   }
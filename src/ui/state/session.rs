@@ -1,37 +1,97 @@
-use std::{collections::BTreeMap, fs::Metadata, iter, path::PathBuf, time::SystemTime};
+use std::{collections::BTreeMap, fs::Metadata, iter, mem, path::PathBuf, time::SystemTime};
 
 use serde::{Deserialize, Serialize};
 
 use super::destination::Destination;
-use crate::{error::Result, utils};
+use crate::{
+  error::Result,
+  reader::places::{self, Place},
+  utils,
+};
 
 pub type Date = String;
 
+// the date of files whose recording date couldn't be inferred.
+pub const UNKNOWN_DATE: &str = "?";
+
 #[derive(Clone)]
 pub struct Session {
   pub date: Date,
   pub files: BTreeMap<SystemTime, File>,
 
+  // the place nearest to the first GPS fix of the session.
+  pub place: Option<Place>,
+
   pub destination: Option<Destination>,
 }
 
 impl Session {
   pub fn new(date: Date, files: Vec<File>, destination: Option<Destination>) -> Result<Self> {
-    let times: Result<Vec<_>> = files.iter().map(File::time).collect();
-
-    Ok(Self {
+    let mut session = Self {
       date,
-      files: iter::zip(times?, files).collect(),
+      files: BTreeMap::new(),
+      place: None,
 
       destination,
-    })
+    };
+
+    for file in files {
+      session.insert_file(file)?;
+    }
+
+    Ok(session)
   }
 
+  // Inserts a file, joining it with the other chapters of its recording when they are in the session.
   pub fn insert_file(&mut self, file: File) -> Result<()> {
+    let recording = file.chapter().map(|(recording, _)| recording);
+    let time = self
+      .files
+      .iter()
+      .find(|(_, other)| recording.is_some() && other.chapter().map(|(recording, _)| recording) == recording)
+      .map(|(time, _)| *time);
+
+    let file = match time.and_then(|time| self.files.remove(&time)) {
+      Some(other) => other.join(file),
+      None => file,
+    };
+
     self.files.insert(file.time()?, file);
+    self.place = self.locate();
 
     Ok(())
   }
+
+  // The name of the directory the session is imported into, from a template where `{date}`,
+  // `{place}` and `{country}` are replaced. Separators left around missing places are trimmed,
+  // and a template that renders to nothing falls back to the date.
+  pub fn dir_name(&self, template: &str) -> String {
+    let (place, country) = self.place.as_ref().map_or(("", ""), |place| (&place.name, &place.country));
+
+    let dir_name = template
+      .replace("{date}", &self.date)
+      .replace("{place}", place)
+      .replace("{country}", country)
+      .trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == ',')
+      .to_string();
+
+    if dir_name.is_empty() {
+      self.date.clone()
+    } else {
+      dir_name
+    }
+  }
+
+  fn locate(&self) -> Option<Place> {
+    let (latitude, longitude) = self.files.values().flat_map(File::recording).find_map(|file| file.route.first())?;
+
+    places::nearest(*latitude, *longitude)
+  }
+
+  // The combined stats of every file with GPS data.
+  pub fn stats(&self) -> Option<Stats> {
+    merge_stats(self.files.values().flat_map(File::recording))
+  }
 }
 
 #[derive(Clone)]
@@ -39,10 +99,27 @@ pub struct File {
   pub path: PathBuf,
   pub metadata: Metadata,
   pub date: Date,
+  pub timezone: Option<String>,
   pub seconds: f64,
+  pub video: Option<Video>,
+  pub camera: Option<Camera>,
+  pub stats: Option<Stats>,
+  pub profile: Option<Profile>,
+
+  // downsampled `(latitude, longitude)` of the file's GPS fixes.
+  pub route: Vec<(f64, f64)>,
+
+  // HiLight tags in seconds from the start of the file, sorted.
+  pub highlights: Vec<f64>,
+
+  pub sidecars: Vec<Sidecar>,
 
   pub note: Option<String>,
   pub status: Option<Status>,
+
+  // the following chapters of the recording this file starts, in order. they share its note
+  // and status.
+  pub chapters: Vec<File>,
 }
 
 impl File {
@@ -53,6 +130,264 @@ impl File {
   pub fn name(&self) -> Result<String> {
     utils::file_name(&self.path)
   }
+
+  // The recording this file belongs to and its chapter number.
+  pub fn chapter(&self) -> Option<(String, u32)> {
+    utils::chapter(&self.name().ok()?)
+  }
+
+  // This file followed by the other chapters of its recording.
+  pub fn recording(&self) -> impl Iterator<Item = &File> {
+    iter::once(self).chain(&self.chapters)
+  }
+
+  pub fn set_status(&mut self, status: Option<Status>) {
+    for chapter in &mut self.chapters {
+      chapter.status = status.clone();
+    }
+
+    self.status = status;
+  }
+
+  pub fn set_note(&mut self, note: Option<String>) {
+    for chapter in &mut self.chapters {
+      chapter.note = note.clone();
+    }
+
+    self.note = note;
+  }
+
+  // Bytes, of every chapter of the recording.
+  pub fn recording_size(&self) -> u64 {
+    self.recording().map(|chapter| chapter.metadata.len()).sum()
+  }
+
+  pub fn recording_seconds(&self) -> f64 {
+    self.recording().map(|chapter| chapter.seconds).sum()
+  }
+
+  pub fn recording_stats(&self) -> Option<Stats> {
+    merge_stats(self.recording())
+  }
+
+  pub fn recording_route(&self) -> Vec<(f64, f64)> {
+    self.recording().flat_map(|chapter| chapter.route.iter().copied()).collect()
+  }
+
+  // HiLight tags in seconds from the start of the recording.
+  pub fn recording_highlights(&self) -> Vec<f64> {
+    let mut offset = 0.0;
+    let mut highlights = Vec::new();
+
+    for chapter in self.recording() {
+      highlights.extend(chapter.highlights.iter().map(|highlight| offset + highlight));
+      offset += chapter.seconds;
+    }
+
+    highlights
+  }
+
+  // The profile of the recording, taking each slice from the chapter that covers it.
+  pub fn recording_profile(&self) -> Option<Profile> {
+    if self.chapters.is_empty() {
+      return self.profile.clone();
+    }
+
+    let seconds = self.recording_seconds();
+    let mut profile = Profile {
+      speed: Vec::new(),
+      altitude: Vec::new(),
+    };
+
+    for slice in 0..Profile::SAMPLES {
+      let mut time = (slice as f64 + 0.5) * seconds / Profile::SAMPLES as f64;
+      let mut chapters = self.recording().peekable();
+
+      while let Some(chapter) = chapters.next() {
+        if time < chapter.seconds || chapters.peek().is_none() {
+          if let Some(Profile { speed, altitude }) = &chapter.profile {
+            let sample = ((time / chapter.seconds * speed.len() as f64) as usize).min(speed.len().saturating_sub(1));
+
+            profile.speed.extend(speed.get(sample));
+            profile.altitude.extend(altitude.get(sample));
+          }
+
+          break;
+        }
+
+        time -= chapter.seconds;
+      }
+    }
+
+    (!profile.speed.is_empty()).then_some(profile)
+  }
+
+  // Joins another chapter of the same recording. The earliest chapter becomes the head of the
+  // recording, and the first note and status of its chapters are kept for all of them.
+  fn join(self, other: File) -> File {
+    let mut chapters: Vec<File> = self.into_chapters().chain(other.into_chapters()).collect();
+    chapters.sort_by_key(|chapter| chapter.chapter().map(|(_, chapter)| chapter));
+
+    let note = chapters.iter().find_map(|chapter| chapter.note.clone());
+    let status = chapters.iter().find_map(|chapter| chapter.status.clone());
+
+    let mut head = chapters.remove(0);
+    head.chapters = chapters;
+    head.set_note(note);
+    head.set_status(status);
+
+    head
+  }
+
+  fn into_chapters(mut self) -> impl Iterator<Item = File> {
+    let chapters = mem::take(&mut self.chapters);
+
+    iter::once(self).chain(chapters)
+  }
+
+  // The key this file is cached by, its media unique ID when known, otherwise its name.
+  pub fn key(&self) -> Result<String> {
+    match self.camera.as_ref().and_then(|camera| camera.media_id.clone()) {
+      Some(media_id) => Ok(media_id),
+      None => self.name(),
+    }
+  }
+}
+
+// The combined stats of files with GPS data, weighted by their durations.
+fn merge_stats<'a>(files: impl Iterator<Item = &'a File>) -> Option<Stats> {
+  files
+    .filter_map(|file| file.stats.as_ref().map(|stats| (stats, file.seconds)))
+    .fold(None, |total: Option<(Stats, f64)>, (stats, seconds)| match total {
+      None => Some((stats.clone(), seconds)),
+      Some((total, total_seconds)) => Some((total.merge(total_seconds, stats, seconds), total_seconds + seconds)),
+    })
+    .map(|(stats, _)| stats)
+}
+
+// A file the camera writes next to a video, named after it.
+#[derive(Clone)]
+pub struct Sidecar {
+  pub kind: SidecarKind,
+  pub path: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SidecarKind {
+  // low resolution proxy.
+  Lrv,
+  // thumbnail.
+  Thm,
+  // audio, e.g. from a media mod microphone.
+  Wav,
+}
+
+impl SidecarKind {
+  pub const ALL: [SidecarKind; 3] = [SidecarKind::Lrv, SidecarKind::Thm, SidecarKind::Wav];
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      SidecarKind::Lrv => "LRV",
+      SidecarKind::Thm => "THM",
+      SidecarKind::Wav => "WAV",
+    }
+  }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Video {
+  pub width: u32,
+  pub height: u32,
+  pub frame_rate: f64,
+  pub codec: String,
+
+  // bits per second.
+  pub bitrate: u64,
+}
+
+// Trip statistics computed from the valid GPS fixes of a file.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Stats {
+  // meters.
+  pub distance: f64,
+
+  // meters per second, from the 2D speed of each fix.
+  pub max_speed: f64,
+  pub avg_speed: f64,
+
+  // meters, ignoring changes smaller than the GPS's vertical noise.
+  pub elevation_gain: f64,
+  pub elevation_loss: f64,
+
+  pub bounds: Bounds,
+}
+
+impl Stats {
+  // Combines the stats of two files, weighting their average speeds by their durations.
+  pub fn merge(&self, seconds: f64, other: &Self, other_seconds: f64) -> Self {
+    let total_seconds = seconds + other_seconds;
+    let avg_speed = if total_seconds > 0.0 {
+      (self.avg_speed * seconds + other.avg_speed * other_seconds) / total_seconds
+    } else {
+      (self.avg_speed + other.avg_speed) / 2.0
+    };
+
+    Self {
+      distance: self.distance + other.distance,
+      max_speed: self.max_speed.max(other.max_speed),
+      avg_speed,
+      elevation_gain: self.elevation_gain + other.elevation_gain,
+      elevation_loss: self.elevation_loss + other.elevation_loss,
+      bounds: self.bounds.union(&other.bounds),
+    }
+  }
+}
+
+// The speed and altitude of a file over time, in `SAMPLES` equal slices.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Profile {
+  // meters per second.
+  pub speed: Vec<f64>,
+
+  // meters.
+  pub altitude: Vec<f64>,
+}
+
+impl Profile {
+  pub const SAMPLES: usize = 16;
+}
+
+// A bounding box in degrees.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Bounds {
+  pub min_latitude: f64,
+  pub min_longitude: f64,
+  pub max_latitude: f64,
+  pub max_longitude: f64,
+}
+
+impl Bounds {
+  pub fn union(&self, other: &Self) -> Self {
+    Self {
+      min_latitude: self.min_latitude.min(other.min_latitude),
+      min_longitude: self.min_longitude.min(other.min_longitude),
+      max_latitude: self.max_latitude.max(other.max_latitude),
+      max_longitude: self.max_longitude.max(other.max_longitude),
+    }
+  }
+}
+
+// The camera that recorded a file, from its udta metadata.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Camera {
+  pub firmware: Option<String>,
+  pub model: Option<String>,
+  pub lens: Option<String>,
+  // the camera serial number (CASN), the same as in MISC/version.txt.
+  pub serial: Option<String>,
+
+  // the media unique ID (MUID) of the file, in hex.
+  pub media_id: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -60,3 +395,100 @@ pub enum Status {
   Import,
   Ignore,
 }
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs, process, thread, time::Duration};
+
+  use super::*;
+
+  // Files are keyed by their creation time, so each fixture is written to disk.
+  fn file(dir: &str, name: &str) -> File {
+    let dir = temp_dir(dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    fs::write(&path, name).unwrap();
+    thread::sleep(Duration::from_millis(10));
+
+    File {
+      metadata: fs::metadata(&path).unwrap(),
+      path,
+      date: "2023-04-05".to_string(),
+      timezone: None,
+      seconds: 60.0,
+      video: None,
+      camera: None,
+      stats: None,
+      profile: None,
+      route: Vec::new(),
+      highlights: Vec::new(),
+      sidecars: Vec::new(),
+      note: None,
+      status: None,
+      chapters: Vec::new(),
+    }
+  }
+
+  fn temp_dir(dir: &str) -> PathBuf {
+    env::temp_dir().join(format!("gopro-importer-{}-{}", dir, process::id()))
+  }
+
+  fn names(session: &Session) -> Vec<Vec<String>> {
+    session
+      .files
+      .values()
+      .map(|file| file.recording().map(|chapter| chapter.name().unwrap()).collect())
+      .collect()
+  }
+
+  #[test]
+  fn joins_chapters_in_any_order() {
+    let first = file("chapters", "GX010123.MP4");
+    let second = file("chapters", "GX020123.MP4");
+    let third = file("chapters", "GX030123.MP4");
+    let other = file("chapters", "GX010124.MP4");
+
+    let session = Session::new("2023-04-05".to_string(), vec![third, other, first, second], None).unwrap();
+
+    assert_eq!(
+      names(&session),
+      vec![vec!["GX010123.MP4", "GX020123.MP4", "GX030123.MP4"], vec!["GX010124.MP4"]]
+    );
+
+    fs::remove_dir_all(temp_dir("chapters")).unwrap();
+  }
+
+  #[test]
+  fn joined_chapters_share_note_and_status() {
+    let first = file("notes", "GH010042.MP4");
+    let mut second = file("notes", "GH020042.MP4");
+    second.note = Some("summit".to_string());
+    second.status = Some(Status::Import);
+    second.highlights = vec![5.0];
+
+    let mut session = Session::new("2023-04-05".to_string(), vec![second], None).unwrap();
+    session.insert_file(first).unwrap();
+
+    let recording = session.files.values().next().unwrap();
+    assert_eq!(session.files.len(), 1);
+    assert_eq!(recording.name().unwrap(), "GH010042.MP4");
+    assert!(recording.recording().all(|chapter| chapter.note.as_deref() == Some("summit")));
+    assert!(recording.recording().all(|chapter| chapter.status == Some(Status::Import)));
+    assert_eq!(recording.recording_seconds(), 120.0);
+    assert_eq!(recording.recording_highlights(), vec![65.0]);
+
+    fs::remove_dir_all(temp_dir("notes")).unwrap();
+  }
+
+  #[test]
+  fn files_without_chapters_are_not_joined() {
+    let files = vec![file("unchaptered", "clip.MP4"), file("unchaptered", "other.MP4")];
+    let session = Session::new("2023-04-05".to_string(), files, None).unwrap();
+
+    assert_eq!(session.files.len(), 2);
+    assert!(session.files.values().all(|file| file.chapters.is_empty()));
+
+    fs::remove_dir_all(temp_dir("unchaptered")).unwrap();
+  }
+}
@@ -0,0 +1,18 @@
+// What the sparkline column of the files table shows.
+#[derive(PartialEq, Eq, Default, Clone, Copy)]
+pub enum Sparkline {
+  #[default]
+  Speed,
+  Altitude,
+  Hidden,
+}
+
+impl Sparkline {
+  pub fn next(self) -> Self {
+    match self {
+      Sparkline::Speed => Sparkline::Altitude,
+      Sparkline::Altitude => Sparkline::Hidden,
+      Sparkline::Hidden => Sparkline::Speed,
+    }
+  }
+}
@@ -11,3 +11,58 @@ pub fn file_name(path: &Path) -> Result<String> {
       .to_string(),
   )
 }
+
+// `name` with the characters that aren't allowed in file names on common filesystems replaced.
+pub fn safe_file_name(name: &str) -> String {
+  let name: String = name
+    .chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_control() => '_',
+      c => c,
+    })
+    .collect();
+
+  match name.trim_matches(|c: char| c == '.' || c.is_whitespace()) {
+    "" => "_".to_string(),
+    name => name.to_string(),
+  }
+}
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+// Great-circle distance in meters between two `(latitude, longitude)` pairs in degrees.
+pub fn distance((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+  let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+  let (dlat, dlon) = (lat2 - lat1, (lon2 - lon1).to_radians());
+
+  let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+  2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+// The recording a GoPro file belongs to and its chapter number. Long recordings are split into
+// chapters named GX010123.MP4, GX020123.MP4, ... while older cameras use GOPR0123.MP4 for the
+// first chapter and GP010123.MP4, GP020123.MP4, ... for the rest.
+pub fn chapter(file_name: &str) -> Option<(String, u32)> {
+  let stem = file_name.split('.').next()?;
+  if stem.len() != 8 || !stem.is_ascii() {
+    return None;
+  }
+
+  let (prefix, number) = stem.split_at(4);
+  if !number.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+
+  if prefix == "GOPR" {
+    return Some((format!("GP{}", number), 0));
+  }
+
+  let (letters, chapter) = prefix.split_at(2);
+  if !letters.bytes().all(|b| b.is_ascii_uppercase()) {
+    return None;
+  }
+
+  Some((format!("{}{}", letters, number), chapter.parse().ok()?))
+}
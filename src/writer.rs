@@ -1,13 +1,14 @@
-use std::{fs, sync::mpsc::Sender, thread};
+use std::{fs, iter, sync::mpsc::Sender, thread};
 
 use crate::{
+  cache::{self, Import},
   error::Result,
   ui::{
     events::Event,
     state::{
       destination::Destination,
       progress::Progress,
-      session::{Date, File, Session, Status as FileStatus},
+      session::{File, Session, Status as FileStatus},
     },
   },
 };
@@ -21,28 +22,28 @@ impl Writer {
     Self { event_sender }
   }
 
-  pub fn spawn(&self, sessions: Vec<Session>) -> Progress {
+  pub fn spawn(&self, sessions: Vec<Session>, session_dir: &str) -> Progress {
     let files: Vec<FileToImport> = sessions
       .into_iter()
       .filter_map(|session| {
+        let dir_name = session.dir_name(session_dir);
+
         if let Some(destination) = session.destination {
-          Some((session.files, destination, session.date))
+          Some((session.files, destination, dir_name))
         } else {
           None
         }
       })
-      .flat_map(|(files, destination, session_date)| {
-        files.into_values().filter_map(move |file| {
-          if file.status == Some(FileStatus::Import) {
-            Some(FileToImport {
-              file,
-              destination: destination.clone(),
-              session_date: session_date.clone(),
-            })
-          } else {
-            None
-          }
-        })
+      .flat_map(|(files, destination, dir_name)| {
+        files
+          .into_values()
+          .filter(|file| file.status == Some(FileStatus::Import))
+          .flat_map(|file| file.recording().cloned().collect::<Vec<_>>())
+          .map(move |file| FileToImport {
+            file,
+            destination: destination.clone(),
+            dir_name: dir_name.clone(),
+          })
       })
       .collect();
 
@@ -62,18 +63,23 @@ impl Writer {
 struct FileToImport {
   file: File,
   destination: Destination,
-  session_date: Date,
+  dir_name: String,
 }
 
 impl FileToImport {
-  pub fn import(&self) -> Result<()> {
-    if let Some(base) = &self.file.path.file_name() {
-      let destination_dir = self.destination.abs.join(self.session_date.clone());
-      if !destination_dir.exists() {
-        fs::create_dir(&destination_dir)?;
-      };
-
-      fs::copy(&self.file.path, &destination_dir.join(base))?;
+  // Copies the file, and the sidecars the import config asks for.
+  pub fn import(&self, config: &Import) -> Result<()> {
+    let sidecars = self.file.sidecars.iter().filter(|sidecar| config.copies(sidecar.kind));
+
+    let destination_dir = self.destination.abs.join(&self.dir_name);
+    if !destination_dir.exists() {
+      fs::create_dir(&destination_dir)?;
+    };
+
+    for path in iter::once(&self.file.path).chain(sidecars.map(|sidecar| &sidecar.path)) {
+      if let Some(base) = path.file_name() {
+        fs::copy(path, destination_dir.join(base))?;
+      }
     }
 
     Ok(())
@@ -81,9 +87,11 @@ impl FileToImport {
 }
 
 fn run(mut progress: Progress, files: Vec<FileToImport>) -> Result<()> {
+  let config = cache::import_config()?;
+
   for (file_idx, entry) in files.into_iter().enumerate() {
     progress.set_file_idx(file_idx)?;
-    entry.import()?;
+    entry.import(&config)?;
   }
 
   progress.set_done()?;